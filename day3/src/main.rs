@@ -1,55 +1,46 @@
-use regex::Regex;
+mod tokenizer;
+
+use std::{fs::File, io::Read};
+
+use tokenizer::{Instruction, Tokenizer};
 
 fn main() {
-    let input = shared::read_file_from_args();
+    let filename = shared::filename_from_args();
+    let open = || File::open(&filename).expect("Failed to open file");
 
-    let result_one = part_one(&input);
+    let result_one = part_one(open());
     println!("Part 1: {}", result_one);
 
-    let result_two = part_two(&input);
+    let result_two = part_two(open());
     println!("Part 2: {}", result_two);
 }
 
-fn part_one(input: &str) -> usize {
-    let re = Regex::new(r#"mul\((\d*),(\d*)\)"#).expect("Unable to parse RegEx");
-
-    re.captures_iter(input)
-        .map(|capture| {
-            let a = capture[1]
-                .parse::<usize>()
-                .expect("Unable to parse first multiplier");
-            let b = capture[2]
-                .parse::<usize>()
-                .expect("Unable to parse second multiplier");
-            a * b
+/// The totals are `u128` so multi-gigabyte inputs can't overflow them.
+fn part_one(input: impl Read) -> u128 {
+    Tokenizer::new(input)
+        .map(|instruction| match instruction {
+            Instruction::Mul(a, b) => (a * b) as u128,
+            _ => 0,
         })
         .sum()
 }
 
-fn part_two(input: &str) -> usize {
+fn part_two(input: impl Read) -> u128 {
     let mut enabled = true;
 
-    let re = Regex::new(r#"do\(\)|don't\(\)|mul\((\d*),(\d*)\)"#).unwrap();
-
-    re.captures_iter(input)
-        .map(|capture| match &capture[0] {
-            "do()" => {
+    Tokenizer::new(input)
+        .map(|instruction| match instruction {
+            Instruction::Do => {
                 enabled = true;
                 0
             }
-            "don't()" => {
+            Instruction::Dont => {
                 enabled = false;
                 0
             }
-            _ => {
+            Instruction::Mul(a, b) => {
                 if enabled {
-                    let a = capture[1]
-                        .parse::<usize>()
-                        .expect("Unable to parse first multiplier");
-                    let b = capture[2]
-                        .parse::<usize>()
-                        .expect("Unable to parse second multiplier");
-                    a * b
+                    (a * b) as u128
                 } else {
                     0
                 }
@@ -68,7 +59,7 @@ mod tests {
 
     #[test]
     fn part_one_works_on_the_example_input() {
-        let result = part_one(PART_ONE_EXAMPLE_INPUT.trim());
+        let result = part_one(PART_ONE_EXAMPLE_INPUT.trim().as_bytes());
         assert_eq!(result, 161);
     }
 
@@ -78,7 +69,7 @@ mod tests {
 
     #[test]
     fn part_two_works_on_the_example_input() {
-        let result = part_two(PART_TWO_EXAMPLE_INPUT.trim());
+        let result = part_two(PART_TWO_EXAMPLE_INPUT.trim().as_bytes());
         assert_eq!(result, 48);
     }
 }
@@ -0,0 +1,279 @@
+use std::io::{ErrorKind, Read};
+
+/// The number of bytes read from the input at a time.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// An instruction found in the corrupted memory.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Instruction {
+    /// `mul(a,b)`
+    Mul(usize, usize),
+
+    /// `do()`
+    Do,
+
+    /// `don't()`
+    Dont,
+}
+
+/// Where the tokenizer is within a possible instruction.
+#[derive(Clone, Copy, Debug)]
+enum State {
+    /// Not in an instruction
+    Start,
+
+    /// Part way through some fixed text, e.g. `mul(` or `don't()`
+    Text { text: &'static [u8], matched: usize },
+
+    /// Reading the first number of a `mul`
+    First(Number),
+
+    /// Reading the second number of a `mul`, after the first
+    Second(usize, Number),
+}
+
+/// A number being read, and how many digits it has so far.
+#[derive(Clone, Copy, Debug, Default)]
+struct Number {
+    value: usize,
+    digits: usize,
+}
+
+impl Number {
+    /// Add a digit to the end of the number, if it has room for another.
+    fn push(self, digit: u8) -> Option<Number> {
+        if self.digits == MAX_DIGITS {
+            return None;
+        }
+
+        Some(Number {
+            value: self.value * 10 + (digit - b'0') as usize,
+            digits: self.digits + 1,
+        })
+    }
+}
+
+/// The most digits a number in a `mul` can have.
+const MAX_DIGITS: usize = 3;
+
+const MUL: &[u8] = b"mul(";
+const DO: &[u8] = b"do()";
+const DONT: &[u8] = b"don't()";
+
+/// Finds the instructions in corrupted memory, reading it a chunk at a time
+/// so the whole input never has to be in memory. Instructions can straddle
+/// the chunks.
+///
+/// Numbers must have one to three digits, as in the puzzle. Anything that
+/// isn't exactly an instruction is skipped, e.g. `mul(4*`, `mul ( 2 , 4 )`
+/// or `do_not()`.
+///
+/// ```text
+/// xmul(2,4)&mul[3,7]!^don't()_mul(5,5)
+/// ```
+///
+/// contains `Mul(2, 4)`, `Dont` and `Mul(5, 5)`.
+pub struct Tokenizer<R: Read> {
+    reader: R,
+    chunk: Vec<u8>,
+    position: usize,
+    length: usize,
+    state: State,
+}
+
+impl<R: Read> Tokenizer<R> {
+    pub fn new(reader: R) -> Self {
+        Tokenizer::with_chunk_size(reader, CHUNK_SIZE)
+    }
+
+    pub fn with_chunk_size(reader: R, chunk_size: usize) -> Self {
+        assert!(chunk_size > 0, "Chunk size must be at least 1");
+
+        Tokenizer {
+            reader,
+            chunk: vec![0; chunk_size],
+            position: 0,
+            length: 0,
+            state: State::Start,
+        }
+    }
+
+    /// The next byte of the input, reading the next chunk when this one runs
+    /// out.
+    fn next_byte(&mut self) -> Option<u8> {
+        while self.position == self.length {
+            match self.reader.read(&mut self.chunk) {
+                Ok(0) => return None,
+                Ok(length) => {
+                    self.position = 0;
+                    self.length = length;
+                }
+                Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                Err(err) => panic!("Failed to read input: {}", err),
+            }
+        }
+
+        let byte = self.chunk[self.position];
+        self.position += 1;
+
+        Some(byte)
+    }
+
+    /// Move on by a byte, returning the instruction it completes, if any.
+    fn step(&mut self, byte: u8) -> Option<Instruction> {
+        let (state, instruction) = match self.state {
+            State::Start => return self.start(byte),
+
+            State::Text { text, matched } if text[matched] == byte => {
+                if matched + 1 < text.len() {
+                    let state = State::Text {
+                        text,
+                        matched: matched + 1,
+                    };
+                    (state, None)
+                } else if text == MUL {
+                    (State::First(Number::default()), None)
+                } else if text == DO {
+                    (State::Start, Some(Instruction::Do))
+                } else {
+                    (State::Start, Some(Instruction::Dont))
+                }
+            }
+
+            // "do" could still be the start of "don't()"
+            State::Text { text, matched: 2 } if text == DO && byte == DONT[2] => {
+                let state = State::Text {
+                    text: DONT,
+                    matched: 3,
+                };
+                (state, None)
+            }
+
+            State::First(number) if byte.is_ascii_digit() => match number.push(byte) {
+                Some(number) => (State::First(number), None),
+                None => (State::Start, None),
+            },
+
+            State::First(a) if a.digits > 0 && byte == b',' => {
+                (State::Second(a.value, Number::default()), None)
+            }
+
+            State::Second(a, number) if byte.is_ascii_digit() => match number.push(byte) {
+                Some(number) => (State::Second(a, number), None),
+                None => (State::Start, None),
+            },
+
+            State::Second(a, b) if b.digits > 0 && byte == b')' => {
+                (State::Start, Some(Instruction::Mul(a, b.value)))
+            }
+
+            // Not an instruction after all, but this byte could start one
+            _ => return self.start(byte),
+        };
+
+        self.state = state;
+        instruction
+    }
+
+    /// Start a new instruction from the byte, if it can begin one.
+    fn start(&mut self, byte: u8) -> Option<Instruction> {
+        self.state = match byte {
+            b'm' => State::Text {
+                text: MUL,
+                matched: 1,
+            },
+            b'd' => State::Text {
+                text: DO,
+                matched: 1,
+            },
+            _ => State::Start,
+        };
+
+        None
+    }
+}
+
+impl<R: Read> Iterator for Tokenizer<R> {
+    type Item = Instruction;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(byte) = self.next_byte() {
+            if let Some(instruction) = self.step(byte) {
+                return Some(instruction);
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokenize(input: &str, chunk_size: usize) -> Vec<Instruction> {
+        Tokenizer::with_chunk_size(input.as_bytes(), chunk_size).collect()
+    }
+
+    #[test]
+    fn test_instructions() {
+        let input = "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))";
+
+        assert_eq!(
+            tokenize(input, CHUNK_SIZE),
+            vec![
+                Instruction::Mul(2, 4),
+                Instruction::Dont,
+                Instruction::Mul(5, 5),
+                Instruction::Mul(11, 8),
+                Instruction::Do,
+                Instruction::Mul(8, 5),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_instructions_straddling_chunks() {
+        let input = "mul(123,45)don't()do()mmul(1,2)ddo()mul(007,999)";
+        let expected = tokenize(input, CHUNK_SIZE);
+
+        assert_eq!(expected.len(), 6);
+        assert_eq!(expected[5], Instruction::Mul(7, 999));
+
+        for chunk_size in 1..=input.len() {
+            assert_eq!(tokenize(input, chunk_size), expected, "{}", chunk_size);
+        }
+    }
+
+    #[test]
+    fn test_restarts_after_a_failed_instruction() {
+        assert_eq!(tokenize("mulmul(2,3)", 4), vec![Instruction::Mul(2, 3)]);
+        assert_eq!(
+            tokenize("mul(2,do()mul(4,don't()", 4),
+            vec![Instruction::Do, Instruction::Dont]
+        );
+        assert_eq!(tokenize("dodon't()", 4), vec![Instruction::Dont]);
+    }
+
+    #[test]
+    fn test_not_instructions() {
+        let inputs = [
+            "mul(,3)",
+            "mul(3,)",
+            "mul(3)",
+            "mul(2,3",
+            "mul (2,3)",
+            "MUL(2,3)",
+            "do_not()",
+            "don't(",
+            "do)",
+            "mul(1234,5)",
+            "mul(5,1234)",
+            "mul(9999999999,9999999999)",
+        ];
+
+        for input in inputs {
+            assert_eq!(tokenize(input, 3), vec![], "{}", input);
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use std::collections::{HashMap, VecDeque};
+
+/// An Aho-Corasick automaton, which finds every occurrence of many patterns
+/// in a single pass over some text.
+pub struct Automaton {
+    nodes: Vec<Node>,
+    pattern_lengths: Vec<usize>,
+}
+
+#[derive(Default)]
+struct Node {
+    next: HashMap<char, usize>,
+
+    // The node for the longest proper suffix of this node's text that is also
+    // a prefix of some pattern
+    fail: usize,
+
+    // The patterns ending at this node, including through the fail links
+    outputs: Vec<usize>,
+}
+
+impl Automaton {
+    pub fn new<S: AsRef<str>>(patterns: &[S]) -> Self {
+        let mut nodes = vec![Node::default()];
+        let mut pattern_lengths = Vec::with_capacity(patterns.len());
+
+        // Build a trie of the patterns
+        for (idx, pattern) in patterns.iter().enumerate() {
+            let mut node = 0;
+
+            for c in pattern.as_ref().chars() {
+                node = match nodes[node].next.get(&c) {
+                    Some(&next) => next,
+                    None => {
+                        nodes.push(Node::default());
+                        let next = nodes.len() - 1;
+                        nodes[node].next.insert(c, next);
+                        next
+                    }
+                };
+            }
+
+            // An empty pattern would be found between every character
+            if node != 0 {
+                nodes[node].outputs.push(idx);
+            }
+
+            pattern_lengths.push(pattern.as_ref().chars().count());
+        }
+
+        // Link each node to its fail node, breadth first so shorter suffixes
+        // are always linked first
+        let mut queue: VecDeque<usize> = nodes[0].next.values().copied().collect();
+
+        while let Some(node) = queue.pop_front() {
+            let children: Vec<(char, usize)> =
+                nodes[node].next.iter().map(|(&c, &n)| (c, n)).collect();
+
+            for (c, child) in children {
+                let mut fail = nodes[node].fail;
+
+                while fail != 0 && !nodes[fail].next.contains_key(&c) {
+                    fail = nodes[fail].fail;
+                }
+
+                let fail = nodes[fail].next.get(&c).copied().unwrap_or(0);
+
+                nodes[child].fail = fail;
+
+                let inherited = nodes[fail].outputs.clone();
+                nodes[child].outputs.extend(inherited);
+
+                queue.push_back(child);
+            }
+        }
+
+        Automaton {
+            nodes,
+            pattern_lengths,
+        }
+    }
+
+    /// The number of characters in a pattern.
+    pub fn pattern_len(&self, pattern: usize) -> usize {
+        self.pattern_lengths[pattern]
+    }
+
+    /// Find every occurrence of every pattern, as the index of the pattern
+    /// and the index in the text of its last character. Empty patterns are
+    /// never found.
+    pub fn find_all(&self, text: impl IntoIterator<Item = char>) -> Vec<(usize, usize)> {
+        let mut found = Vec::new();
+        let mut node = 0;
+
+        for (idx, c) in text.into_iter().enumerate() {
+            while node != 0 && !self.nodes[node].next.contains_key(&c) {
+                node = self.nodes[node].fail;
+            }
+
+            node = self.nodes[node].next.get(&c).copied().unwrap_or(0);
+
+            found.extend(
+                self.nodes[node]
+                    .outputs
+                    .iter()
+                    .map(|&pattern| (pattern, idx)),
+            );
+        }
+
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_all() {
+        let automaton = Automaton::new(&["he", "she", "his", "hers"]);
+        let mut found = automaton.find_all("ushers".chars());
+        found.sort();
+
+        // "she" and "he" end at 3, "hers" ends at 5
+        assert_eq!(found, vec![(0, 3), (1, 3), (3, 5)]);
+        assert_eq!(automaton.pattern_len(3), 4);
+    }
+
+    #[test]
+    fn test_overlapping_matches() {
+        let automaton = Automaton::new(&["aa", "a"]);
+        let found = automaton.find_all("aaa".chars());
+
+        assert_eq!(found.len(), 5);
+    }
+
+    #[test]
+    fn test_no_patterns() {
+        let automaton = Automaton::new::<&str>(&[]);
+        assert_eq!(automaton.find_all("XMAS".chars()), vec![]);
+
+        let automaton = Automaton::new(&[""]);
+        assert_eq!(automaton.find_all("XMAS".chars()), vec![]);
+    }
+}
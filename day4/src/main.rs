@@ -1,44 +1,161 @@
+mod automaton;
+mod paths;
 mod puzzle;
+mod render;
+mod stencil;
 
-use puzzle::Puzzle;
+use std::fs;
+
+use paths::{PathMatch, PathMode};
+use puzzle::{Coord, Direction, Match, Puzzle, RaggedRows};
+use render::render;
+use stencil::{Stencil, StencilMatch};
 
 fn main() {
     let input = shared::read_file_from_args();
-    let puzzle = parse_input(&input);
+    let puzzle = match shared::flag_value("--ragged") {
+        Some(ragged) => parse_input_with(&input, RaggedRows::from(ragged.as_str())),
+        None => parse_input(&input),
+    };
+
+    if let Some(words) = words_from_args() {
+        match shared::flag_value("--mode") {
+            Some(mode) => search_paths(&puzzle, &words, PathMode::from(mode.as_str())),
+            None => search(&puzzle, &words),
+        }
+        return;
+    }
+
+    if let Some(stencil) = shared::flag_value("--stencil") {
+        search_stencil(&puzzle, &Stencil::from(stencil.as_str()));
+        return;
+    }
+
+    let xmas: Vec<Match> = xmas_matches(&puzzle);
+    show_matches(&puzzle, xmas.iter().map(Match::coords));
+    println!("Part 1: {}", xmas.len());
+
+    let x_mas: Vec<StencilMatch> = x_mas_matches(&puzzle);
+    show_matches(&puzzle, x_mas.iter().map(StencilMatch::coords));
+    println!("Part 2: {}", x_mas.len());
+}
+
+/// Print the Puzzle with only the matched letters kept, when `--render` is
+/// given, or with each match in its own colour for `--colour`.
+fn show_matches(puzzle: &Puzzle, matches: impl Iterator<Item = Vec<Coord>>) {
+    let colour = shared::has_flag("--colour");
+
+    if colour || shared::has_flag("--render") {
+        let matches: Vec<Vec<Coord>> = matches.collect();
+        println!("{}\n", render(puzzle, &matches, colour));
+    }
+}
 
-    let result_one = part_one(&puzzle);
-    println!("Part 1: {}", result_one);
+/// Search for the words given on the command line, printing every match.
+/// Only searches the `--directions` given, e.g. `--directions=right,down`.
+fn search(puzzle: &Puzzle, words: &[String]) {
+    let directions: Vec<Direction> = match shared::flag_value("--directions") {
+        Some(directions) => directions.split(',').map(Direction::from).collect(),
+        None => Direction::ALL.to_vec(),
+    };
 
-    let result_two = part_two(&puzzle);
-    println!("Part 2: {}", result_two);
+    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+    let matches = puzzle.find_words(&words, &directions);
+    show_matches(puzzle, matches.iter().map(Match::coords));
+
+    for m in matches.iter() {
+        println!(
+            "{} at ({}, {}) going {}",
+            m.word, m.start.0, m.start.1, m.direction
+        );
+    }
+
+    println!("{} matches", matches.len());
 }
 
-fn part_one(puzzle: &Puzzle) -> usize {
-    puzzle
-        .iter_coords()
-        .fold(0, |acc, coord| acc + puzzle.count_xmas_instances_at(coord))
+/// Search for the words given on the command line along wrapping or bending
+/// paths, with `--mode=wrap` or `--mode=bend`, printing every match.
+fn search_paths(puzzle: &Puzzle, words: &[String], mode: PathMode) {
+    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+    let matches: Vec<PathMatch> = puzzle.find_paths(&words, mode);
+    show_matches(puzzle, matches.iter().map(|m| m.path.clone()));
+
+    for m in matches.iter() {
+        println!("{}", m);
+    }
+
+    println!("{} matches", matches.len());
 }
 
-fn part_two(puzzle: &Puzzle) -> usize {
-    puzzle.iter_coords().fold(0, |acc, coord| {
-        acc + if puzzle.has_mas_cross_at(coord) { 1 } else { 0 }
-    })
+/// Search for a stencil given on the command line, e.g.
+/// `--stencil=M.S/.A./M.S`, printing every match.
+fn search_stencil(puzzle: &Puzzle, stencil: &Stencil) {
+    let matches = puzzle.find_stencil(stencil);
+    show_matches(puzzle, matches.iter().map(StencilMatch::coords));
+
+    for m in matches.iter() {
+        println!("{} at ({}, {})", m.variant, m.top_left.0, m.top_left.1);
+    }
+
+    println!("{} matches", matches.len());
+}
+
+/// The words to search for, from `--words=XMAS,SAMX` or one per line in a
+/// `--dictionary=words.txt` file.
+fn words_from_args() -> Option<Vec<String>> {
+    let mut words: Vec<String> = shared::flag_value("--words")
+        .map(|words| words.split(',').map(str::to_string).collect())
+        .unwrap_or_default();
+
+    if let Some(filename) = shared::flag_value("--dictionary") {
+        let dictionary = fs::read_to_string(filename).expect("Failed to read dictionary");
+        words.extend(
+            dictionary
+                .lines()
+                .map(str::trim)
+                .filter(|word| !word.is_empty())
+                .map(str::to_string),
+        );
+    }
+
+    if words.is_empty() {
+        None
+    } else {
+        Some(words)
+    }
+}
+
+fn xmas_matches(puzzle: &Puzzle) -> Vec<Match> {
+    puzzle.find_word("XMAS", &Direction::ALL)
+}
+
+/// The X shapes made of two "MAS", in any orientation.
+fn x_mas_matches(puzzle: &Puzzle) -> Vec<StencilMatch> {
+    puzzle.find_stencil(&Stencil::from("M.S/.A./M.S"))
 }
 
 fn parse_input(input: &str) -> Puzzle {
-    Puzzle::new(
-        input
-            .lines()
-            .map(|line| line.trim().chars().collect())
-            .collect(),
-    )
+    parse_input_with(input, RaggedRows::Reject)
+}
+
+/// Parse the grid, ignoring blank lines before and after it. Rows of
+/// different lengths are rejected or padded, with `--ragged=reject` (the
+/// default) or `--ragged=pad`.
+fn parse_input_with(input: &str, ragged: RaggedRows) -> Puzzle {
+    let rows: Vec<Vec<char>> = input
+        .trim()
+        .lines()
+        .map(|line| line.trim().chars().collect())
+        .collect();
+
+    Puzzle::new(rows, ragged).unwrap_or_else(|err| panic!("Invalid puzzle: {}", err))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    const INPUT: &str = r#"
+    pub(crate) const INPUT: &str = r#"
         MMMSXXMASM
         MSAMXMSMSA
         AMXSXMAAMM
@@ -54,7 +171,7 @@ mod tests {
     #[test]
     fn test_part_one() {
         let puzzle = parse_input(INPUT.trim());
-        let result = part_one(&puzzle);
+        let result = xmas_matches(&puzzle).len();
 
         assert_eq!(result, 18);
     }
@@ -62,8 +179,188 @@ mod tests {
     #[test]
     fn test_part_two() {
         let puzzle = parse_input(INPUT.trim());
-        let result = part_two(&puzzle);
+        let result = x_mas_matches(&puzzle).len();
 
         assert_eq!(result, 9);
     }
+
+    #[test]
+    fn test_find_words() {
+        let puzzle = parse_input(INPUT.trim());
+        let matches = puzzle.find_words(&["XMAS", "MAS", "XMAS"], &Direction::ALL);
+
+        // Repeated words are only searched once
+        assert_eq!(matches.iter().filter(|m| m.word == "XMAS").count(), 18);
+        assert!(matches.iter().any(|m| m.word == "MAS"));
+        assert_eq!(matches[0].word, "XMAS");
+    }
+
+    #[test]
+    fn test_find_word_reports_start_and_direction() {
+        let puzzle = parse_input("..S\n.A.\nM..");
+        let matches = puzzle.find_word("MAS", &Direction::ALL);
+
+        assert_eq!(
+            matches,
+            vec![Match {
+                word: "MAS".to_string(),
+                start: (0, 2),
+                direction: Direction::UpRight,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_find_word_in_some_directions() {
+        let puzzle = parse_input(INPUT.trim());
+
+        let forwards = puzzle.find_word("XMAS", &[Direction::Right]);
+        let backwards = puzzle.find_word("XMAS", &[Direction::Left]);
+
+        assert_eq!(forwards.len(), 3);
+        assert_eq!(backwards.len(), 2);
+        assert!(forwards.iter().all(|m| m.direction == Direction::Right));
+    }
+
+    #[test]
+    fn test_palindromes_are_not_double_counted() {
+        let puzzle = parse_input("RADAR\n.....\nRADAR");
+
+        assert_eq!(puzzle.find_word("RADAR", &Direction::ALL).len(), 2);
+        assert_eq!(puzzle.find_word("RADAR", &[Direction::Left]).len(), 2);
+
+        // A single letter reads the same in every direction
+        assert_eq!(puzzle.find_word("D", &Direction::ALL).len(), 2);
+
+        // Non-palindromes can overlap their reverse, and count separately
+        assert_eq!(puzzle.find_word("RA", &Direction::ALL).len(), 4);
+    }
+
+    /// Find a word by trying every direction from every coords.
+    fn brute_force(puzzle: &Puzzle, word: &str) -> Vec<(Coord, Direction)> {
+        let mut found = Vec::new();
+
+        for start in puzzle.iter_coords() {
+            for direction in Direction::ALL {
+                let mut coord = Some(start);
+
+                let matches = word.chars().all(|c| {
+                    let letter = coord.and_then(|current| puzzle.get(current));
+                    coord = coord.and_then(|current| puzzle.neighbour(current, direction));
+
+                    letter == Some(c)
+                });
+
+                if matches {
+                    found.push((start, direction));
+                }
+            }
+        }
+
+        found
+    }
+
+    #[test]
+    fn test_find_words_matches_brute_force() {
+        // Not palindromes, so every match is reported
+        let words = ["XMAS", "SAMX", "MAS", "AM", "XMASXMAS"];
+
+        let mut state: u64 = 0x2024_0004;
+        let mut random_grid = |width: usize, height: usize| {
+            let rows: Vec<String> = (0..height)
+                .map(|_| {
+                    (0..width)
+                        .map(|_| {
+                            state ^= state << 13;
+                            state ^= state >> 7;
+                            state ^= state << 17;
+                            ['X', 'M', 'A', 'S'][(state % 4) as usize]
+                        })
+                        .collect()
+                })
+                .collect();
+
+            rows.join("\n")
+        };
+
+        let mut inputs = vec![INPUT.trim().to_string()];
+        inputs.extend([(1, 1), (1, 9), (9, 1), (7, 13), (40, 40)].map(|(w, h)| random_grid(w, h)));
+
+        for input in inputs {
+            let puzzle = parse_input(&input);
+            let matches = puzzle.find_words(&words, &Direction::ALL);
+
+            for word in words {
+                let mut found: Vec<(Coord, Direction)> = matches
+                    .iter()
+                    .filter(|m| m.word == word)
+                    .map(|m| (m.start, m.direction))
+                    .collect();
+                let mut expected = brute_force(&puzzle, word);
+
+                found.sort_by_key(|&(start, direction)| (start, direction.to_string()));
+                expected.sort_by_key(|&(start, direction)| (start, direction.to_string()));
+
+                assert_eq!(found, expected, "{} in\n{}", word, input);
+            }
+        }
+    }
+
+    #[test]
+    fn test_ragged_rows() {
+        let rows = || vec!["XMAS".chars().collect(), "XM".chars().collect()];
+
+        assert_eq!(
+            Puzzle::new(rows(), RaggedRows::Reject).err(),
+            Some("Row 2 has 2 letters, expected 4".to_string())
+        );
+
+        let puzzle = Puzzle::new(rows(), RaggedRows::Pad).unwrap();
+        assert_eq!((puzzle.width(), puzzle.height()), (4, 2));
+        assert_eq!(puzzle.get((3, 1)), Some('.'));
+    }
+
+    #[test]
+    #[should_panic(expected = "Row 2 has 3 letters, expected 4")]
+    fn test_parse_input_rejects_ragged_rows() {
+        let _ = parse_input("XMAS\nXMA\n");
+    }
+
+    #[test]
+    fn test_parse_input_ignores_surrounding_blank_lines() {
+        let puzzle = parse_input(INPUT);
+
+        assert_eq!((puzzle.width(), puzzle.height()), (10, 10));
+    }
+
+    #[test]
+    fn test_get_and_neighbours() {
+        let puzzle = parse_input("XMAS\nSAMX");
+
+        assert_eq!(puzzle.get((3, 1)), Some('X'));
+        assert_eq!(puzzle.get((4, 1)), None);
+        assert_eq!(puzzle.get((0, 2)), None);
+
+        assert_eq!(puzzle.neighbour((0, 0), Direction::Left), None);
+        assert_eq!(puzzle.neighbour((0, 0), Direction::DownRight), Some((1, 1)));
+
+        let corner: Vec<(Direction, Coord)> = puzzle.neighbours((3, 0)).collect();
+        assert_eq!(
+            corner,
+            vec![
+                (Direction::Left, (2, 0)),
+                (Direction::DownLeft, (2, 1)),
+                (Direction::Down, (3, 1)),
+            ]
+        );
+        assert_eq!(puzzle.neighbours((1, 1)).count(), 5);
+    }
+
+    #[test]
+    fn test_direction_names() {
+        for direction in Direction::ALL {
+            assert_eq!(Direction::from(direction.to_string().as_str()), direction);
+            assert_eq!(direction.opposite().opposite(), direction);
+        }
+    }
 }
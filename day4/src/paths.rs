@@ -0,0 +1,215 @@
+use std::{collections::HashSet, fmt};
+
+use crate::puzzle::{Coord, Direction, Puzzle};
+
+/// How a word can be laid out in the Puzzle, other than in a straight line.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PathMode {
+    /// In a straight line in any direction, wrapping around the edges as if
+    /// the Puzzle were a torus
+    Wrapping,
+
+    /// Turning in any direction at each letter, like in Boggle
+    Bending,
+}
+
+impl From<&str> for PathMode {
+    fn from(name: &str) -> Self {
+        match name {
+            "wrap" => PathMode::Wrapping,
+            "bend" => PathMode::Bending,
+            _ => panic!("Invalid path mode: {}", name),
+        }
+    }
+}
+
+/// A word found along a path through the Puzzle, from its first letter.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PathMatch {
+    pub word: String,
+    pub path: Vec<Coord>,
+}
+
+impl fmt::Display for PathMatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let path: Vec<String> = self
+            .path
+            .iter()
+            .map(|(x, y)| format!("({}, {})", x, y))
+            .collect();
+
+        write!(f, "{} along {}", self.word, path.join(" "))
+    }
+}
+
+impl Puzzle {
+    /// Find every instance of the words laid out according to the mode. A
+    /// path never uses the same letter twice. Matches are ordered by word,
+    /// then by start position row by row.
+    ///
+    /// As with `find_words`, a palindrome found along the same path in both
+    /// directions is only reported once, from whichever end comes first.
+    pub fn find_paths(&self, words: &[&str], mode: PathMode) -> Vec<PathMatch> {
+        let mut seen_words: HashSet<&str> = HashSet::new();
+        let mut matches = Vec::new();
+
+        for &word in words.iter() {
+            if word.is_empty() || !seen_words.insert(word) {
+                continue;
+            }
+
+            let word_chars: Vec<char> = word.chars().collect();
+            let paths = match mode {
+                PathMode::Wrapping => self.wrapping_paths(&word_chars),
+                PathMode::Bending => self.bending_paths(&word_chars),
+            };
+
+            // Each path is identified by whichever way round sorts first
+            let mut seen: HashSet<Vec<Coord>> = HashSet::new();
+
+            for path in paths {
+                let reversed: Vec<Coord> = path.iter().rev().copied().collect();
+
+                if seen.insert(path.clone().min(reversed)) {
+                    matches.push(PathMatch {
+                        word: word.to_string(),
+                        path,
+                    });
+                }
+            }
+        }
+
+        matches
+    }
+
+    fn wrapping_paths(&self, word: &[char]) -> Vec<Vec<Coord>> {
+        let mut paths = Vec::new();
+
+        for start in self.iter_coords() {
+            for direction in Direction::ALL {
+                let path: Vec<Coord> = std::iter::successors(Some(start), |&coord| {
+                    Some(self.wrapped_neighbour(coord, direction))
+                })
+                .take(word.len())
+                .collect();
+
+                let letters_match = path
+                    .iter()
+                    .zip(word.iter())
+                    .all(|(&coord, &c)| self[coord] == c);
+
+                if letters_match && !reuses_letters(&path) {
+                    paths.push(path);
+                }
+            }
+        }
+
+        paths
+    }
+
+    fn bending_paths(&self, word: &[char]) -> Vec<Vec<Coord>> {
+        let mut paths = Vec::new();
+
+        for start in self.iter_coords() {
+            if self[start] == word[0] {
+                self.extend_path(&mut vec![start], word, &mut paths);
+            }
+        }
+
+        paths
+    }
+
+    /// Depth-first search for the rest of the word from the end of the path,
+    /// adding each complete path to `paths`.
+    fn extend_path(&self, path: &mut Vec<Coord>, word: &[char], paths: &mut Vec<Vec<Coord>>) {
+        if path.len() == word.len() {
+            paths.push(path.clone());
+            return;
+        }
+
+        let last = *path.last().expect("Empty path");
+
+        for (_, next) in self.neighbours(last) {
+            if self[next] == word[path.len()] && !path.contains(&next) {
+                path.push(next);
+                self.extend_path(path, word, paths);
+                path.pop();
+            }
+        }
+    }
+
+    /// The coords next to the coords in a direction, wrapping around to the
+    /// other side of the Puzzle at the edges.
+    fn wrapped_neighbour(&self, (x, y): Coord, direction: Direction) -> Coord {
+        let (dx, dy) = direction.delta();
+
+        (
+            (x as isize + dx as isize).rem_euclid(self.width() as isize) as usize,
+            (y as isize + dy as isize).rem_euclid(self.height() as isize) as usize,
+        )
+    }
+}
+
+fn reuses_letters(path: &[Coord]) -> bool {
+    let unique: HashSet<&Coord> = path.iter().collect();
+    unique.len() < path.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_input;
+
+    #[test]
+    fn test_wrapping() {
+        let puzzle = parse_input("SXMA");
+
+        // Every direction but left and right stays on the same letter, and
+        // the diagonals follow the same path as left or right
+        assert_eq!(
+            puzzle.find_paths(&["XMAS"], PathMode::Wrapping),
+            vec![PathMatch {
+                word: "XMAS".to_string(),
+                path: vec![(1, 0), (2, 0), (3, 0), (0, 0)],
+            }]
+        );
+
+        // Going all the way round would reuse the X
+        assert_eq!(puzzle.find_paths(&["XMASX"], PathMode::Wrapping), vec![]);
+    }
+
+    #[test]
+    fn test_wrapping_finds_straight_matches() {
+        let puzzle = parse_input("MMMSXXMASM\nMSAMXMSMSA\nAMXSXMAAMM\nMSAMASMSMX");
+        let straight = puzzle.find_word("XMAS", &Direction::ALL);
+        let wrapping = puzzle.find_paths(&["XMAS"], PathMode::Wrapping);
+
+        assert!(wrapping.len() > straight.len());
+        assert!(straight
+            .iter()
+            .all(|m| wrapping.iter().any(|w| w.path == m.coords())));
+    }
+
+    #[test]
+    fn test_bending() {
+        let puzzle = parse_input("XM\nSA");
+        let matches = puzzle.find_paths(&["XMAS", "XMASX", "SAX"], PathMode::Bending);
+
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].path, vec![(0, 0), (1, 0), (1, 1), (0, 1)]);
+        assert_eq!(
+            matches[0].to_string(),
+            "XMAS along (0, 0) (1, 0) (1, 1) (0, 1)"
+        );
+        assert_eq!(matches[1].path, vec![(0, 1), (1, 1), (0, 0)]);
+    }
+
+    #[test]
+    fn test_bending_palindromes() {
+        let puzzle = parse_input("AB\nBA");
+
+        // Through either B, each only once even though it reads both ways
+        assert_eq!(puzzle.find_paths(&["ABA"], PathMode::Bending).len(), 2);
+        assert_eq!(puzzle.find_paths(&["A"], PathMode::Bending).len(), 2);
+    }
+}
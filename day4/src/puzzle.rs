@@ -1,25 +1,165 @@
-use std::ops;
+use std::{collections::HashSet, fmt, ops};
 
+use crate::automaton::Automaton;
+
+/// A rectangular grid of letters.
 pub struct Puzzle {
     data: Vec<Vec<char>>,
 }
 
-const SEARCH_STRING: &str = "XMAS";
+/// What to do about rows of different lengths.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RaggedRows {
+    /// Refuse to create the Puzzle
+    Reject,
+
+    /// Pad short rows at the end with `.`, to the length of the longest row
+    Pad,
+}
+
+impl From<&str> for RaggedRows {
+    fn from(name: &str) -> Self {
+        match name {
+            "reject" => RaggedRows::Reject,
+            "pad" => RaggedRows::Pad,
+            _ => panic!("Invalid ragged rows policy: {}", name),
+        }
+    }
+}
+
+const PADDING: char = '.';
+
+/// A direction to read a word in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Direction {
+    UpLeft,
+    Up,
+    UpRight,
+    Left,
+    Right,
+    DownLeft,
+    Down,
+    DownRight,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 8] = [
+        Direction::UpLeft,
+        Direction::Up,
+        Direction::UpRight,
+        Direction::Left,
+        Direction::Right,
+        Direction::DownLeft,
+        Direction::Down,
+        Direction::DownRight,
+    ];
+
+    /// The x and y steps to move one character in this direction.
+    pub fn delta(&self) -> (i8, i8) {
+        match self {
+            Direction::UpLeft => (-1, -1),
+            Direction::Up => (0, -1),
+            Direction::UpRight => (1, -1),
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
+            Direction::DownLeft => (-1, 1),
+            Direction::Down => (0, 1),
+            Direction::DownRight => (1, 1),
+        }
+    }
 
-const DIRECTIONS: [(i8, i8); 8] = [
-    (-1, -1),
-    (0, -1),
-    (1, -1),
-    (-1, 0),
-    (1, 0),
-    (-1, 1),
-    (0, 1),
-    (1, 1),
-];
+    /// The direction pointing the other way.
+    pub fn opposite(&self) -> Direction {
+        match self {
+            Direction::UpLeft => Direction::DownRight,
+            Direction::Up => Direction::Down,
+            Direction::UpRight => Direction::DownLeft,
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+            Direction::DownLeft => Direction::UpRight,
+            Direction::Down => Direction::Up,
+            Direction::DownRight => Direction::UpLeft,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Direction::UpLeft => "up-left",
+            Direction::Up => "up",
+            Direction::UpRight => "up-right",
+            Direction::Left => "left",
+            Direction::Right => "right",
+            Direction::DownLeft => "down-left",
+            Direction::Down => "down",
+            Direction::DownRight => "down-right",
+        }
+    }
+}
+
+impl From<&str> for Direction {
+    fn from(name: &str) -> Self {
+        Direction::ALL
+            .into_iter()
+            .find(|direction| direction.name() == name)
+            .unwrap_or_else(|| panic!("Invalid direction: {}", name))
+    }
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// A word found in the Puzzle, reading from `start` in `direction`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Match {
+    pub word: String,
+    pub start: Coord,
+    pub direction: Direction,
+}
+
+impl Match {
+    /// The coords of each letter of the match, from its first letter.
+    pub fn coords(&self) -> Vec<Coord> {
+        let (dx, dy) = self.direction.delta();
+
+        (0..self.word.chars().count() as isize)
+            .map(|idx| {
+                let x = self.start.0.checked_add_signed(dx as isize * idx);
+                let y = self.start.1.checked_add_signed(dy as isize * idx);
+
+                x.zip(y).expect("Match goes off the Puzzle")
+            })
+            .collect()
+    }
+}
 
 impl Puzzle {
-    pub fn new(data: Vec<Vec<char>>) -> Self {
-        Self { data }
+    /// Create a Puzzle from its rows, making sure every row is the same
+    /// length.
+    pub fn new(mut data: Vec<Vec<char>>, ragged: RaggedRows) -> Result<Self, String> {
+        let width = data.iter().map(Vec::len).max().unwrap_or(0);
+
+        for (y, row) in data.iter_mut().enumerate() {
+            if row.len() == width {
+                continue;
+            }
+
+            match ragged {
+                RaggedRows::Reject => {
+                    return Err(format!(
+                        "Row {} has {} letters, expected {}",
+                        y + 1,
+                        row.len(),
+                        width
+                    ))
+                }
+                RaggedRows::Pad => row.resize(width, PADDING),
+            }
+        }
+
+        Ok(Self { data })
     }
 
     pub fn height(&self) -> usize {
@@ -27,16 +167,37 @@ impl Puzzle {
     }
 
     pub fn width(&self) -> usize {
-        if self.data.is_empty() {
-            return 0;
-        }
+        self.data.first().map_or(0, Vec::len)
+    }
+
+    /// The letter at the coords, if they are within the Puzzle.
+    pub fn get(&self, (x, y): Coord) -> Option<char> {
+        self.data.get(y)?.get(x).copied()
+    }
+
+    /// The coords next to the coords in a direction, if they are within the
+    /// Puzzle.
+    pub fn neighbour(&self, (x, y): Coord, direction: Direction) -> Option<Coord> {
+        let (dx, dy) = direction.delta();
+        let coord = (
+            x.checked_add_signed(dx as isize)?,
+            y.checked_add_signed(dy as isize)?,
+        );
+
+        self.get(coord).map(|_| coord)
+    }
 
-        self.data[0].len()
+    /// The coords next to the coords in every direction that stays within
+    /// the Puzzle.
+    pub fn neighbours(&self, coord: Coord) -> impl Iterator<Item = (Direction, Coord)> + '_ {
+        Direction::ALL
+            .into_iter()
+            .filter_map(move |direction| Some((direction, self.neighbour(coord, direction)?)))
     }
 
     /// Create an iterator that will iterate over all
     /// valid coordinates in the Puzzle.
-    pub fn iter_coords(&self) -> PuzzleCoordIterator {
+    pub fn iter_coords(&self) -> PuzzleCoordIterator<'_> {
         PuzzleCoordIterator {
             puzzle: self,
             next_y: 0,
@@ -44,104 +205,93 @@ impl Puzzle {
         }
     }
 
-    /// Count the number of instances of the word "XMAS" that start at the
-    /// given coordinates.
-    pub fn count_xmas_instances_at(&self, coord: Coord) -> usize {
-        let mut count = 0;
-
-        for (dx, dy) in &DIRECTIONS {
-            if self.has_xmas_in_direction(coord, *dx, *dy) {
-                count += 1;
-            }
-        }
-
-        count
-    }
-
-    /// Determine the word "XMAS" stats at the coords, moving in the direction
-    /// defined by dx and dy.
+    /// Find every instance of the words, reading in any of the directions.
+    /// Matches are ordered by word, then by start position row by row.
     ///
-    /// @param coord The starting coordinate
-    /// @param dx The x direction to move in (1, 0, -1)
-    /// @param dy The y direction to move in (1, 0, -1)
-    fn has_xmas_in_direction(&self, coord: Coord, dx: i8, dy: i8) -> bool {
-        let mut current_coord: Coord = (coord.0, coord.1);
-
-        for search_char_idx in 0..SEARCH_STRING.len() {
-            let expected_char = SEARCH_STRING
-                .chars()
-                .nth(search_char_idx)
-                .expect("Empty search string");
-
-            let found_char = self[current_coord];
-
-            if found_char != expected_char {
-                return false;
+    /// Every row, column and diagonal is read forwards and backwards once,
+    /// looking for all the words at the same time, so the search takes time
+    /// in proportion to the size of the Puzzle however many words there are.
+    ///
+    /// A palindrome (e.g. "RADAR") reads the same both ways along the same
+    /// letters, so it is only reported once, from whichever end comes first.
+    /// The same goes for single letter words in every direction.
+    pub fn find_words(&self, words: &[&str], directions: &[Direction]) -> Vec<Match> {
+        let mut seen_words: HashSet<&str> = HashSet::new();
+        let words: Vec<&str> = words
+            .iter()
+            .copied()
+            .filter(|word| !word.is_empty() && seen_words.insert(word))
+            .collect();
+
+        let automaton = Automaton::new(&words);
+
+        // Each found word as its index, first and last letters, and the
+        // position of its direction in `directions`
+        let mut found: Vec<(usize, Coord, Coord, usize)> = Vec::new();
+
+        for (direction, mut cells) in self.lines() {
+            for direction in [direction, direction.opposite()] {
+                if let Some(rank) = directions.iter().position(|d| *d == direction) {
+                    let letters = cells.iter().map(|&coord| self[coord]);
+
+                    for (word, end_idx) in automaton.find_all(letters) {
+                        let start = cells[end_idx + 1 - automaton.pattern_len(word)];
+                        found.push((word, start, cells[end_idx], rank));
+                    }
+                }
+
+                // Read the line backwards the second time round
+                cells.reverse();
             }
+        }
 
-            if (search_char_idx + 1) == SEARCH_STRING.len() {
-                return true;
-            }
+        found.sort_by_key(|&(word, start, _, rank)| (word, start.1, start.0, rank));
 
-            if (current_coord.0 as i16 + dx as i16) < 0
-                || (current_coord.1 as i16 + dy as i16) < 0
-                || current_coord.0 as i16 + dx as i16 >= self.width() as i16
-                || current_coord.1 as i16 + dy as i16 >= self.height() as i16
-            {
-                return false;
-            }
+        // The letters covered by each match, as its first and last letter
+        let mut seen: HashSet<(usize, Coord, Coord)> = HashSet::new();
 
-            current_coord = (
-                (current_coord.0 as i16 + dx as i16) as usize,
-                (current_coord.1 as i16 + dy as i16) as usize,
-            );
-        }
+        found
+            .into_iter()
+            .filter(|&(word, start, end, _)| seen.insert((word, start.min(end), start.max(end))))
+            .map(|(word, start, _, rank)| Match {
+                word: words[word].to_string(),
+                start,
+                direction: directions[rank],
+            })
+            .collect()
+    }
 
-        false
+    /// Find every instance of a word, reading in any of the directions.
+    pub fn find_word(&self, word: &str, directions: &[Direction]) -> Vec<Match> {
+        self.find_words(&[word], directions)
     }
 
-    /// Determine if there is an X formed by two instances of "MAS" at the
-    /// given Coord. The Coord is defined by the center position of the 'A'.
-    pub fn has_mas_cross_at(&self, coord: Coord) -> bool {
-        if self[coord] != 'A'
-            || coord.0 == 0
-            || coord.1 == 0
-            || coord.0 == self.width() - 1
-            || coord.1 == self.height() - 1
-        {
-            return false;
+    /// Every row, column, diagonal and anti-diagonal of the Puzzle, as the
+    /// coords along it and the direction they are read in.
+    fn lines(&self) -> Vec<(Direction, Vec<Coord>)> {
+        let (width, height) = (self.width(), self.height());
+        if width == 0 {
+            return Vec::new();
         }
 
-        let top_left_char = self[(coord.0 - 1, coord.1 - 1)];
-        let top_right_char = self[(coord.0 + 1, coord.1 - 1)];
-        let bottom_left_char = self[(coord.0 - 1, coord.1 + 1)];
-        let bottom_right_char = self[(coord.0 + 1, coord.1 + 1)];
+        let mut starts: Vec<(Direction, Coord)> = Vec::new();
 
-        let right_down: u8 = if top_left_char == 'M' && bottom_right_char == 'S' {
-            1
-        } else {
-            0
-        };
-
-        let right_up: u8 = if bottom_left_char == 'M' && top_right_char == 'S' {
-            1
-        } else {
-            0
-        };
+        starts.extend((0..height).map(|y| (Direction::Right, (0, y))));
+        starts.extend((0..width).map(|x| (Direction::Down, (x, 0))));
+        starts.extend((0..width).map(|x| (Direction::DownRight, (x, 0))));
+        starts.extend((1..height).map(|y| (Direction::DownRight, (0, y))));
+        starts.extend((0..width).map(|x| (Direction::DownLeft, (x, 0))));
+        starts.extend((1..height).map(|y| (Direction::DownLeft, (width - 1, y))));
 
-        let left_down: u8 = if top_right_char == 'M' && bottom_left_char == 'S' {
-            1
-        } else {
-            0
-        };
+        starts
+            .into_iter()
+            .map(|(direction, start)| {
+                let cells =
+                    std::iter::successors(Some(start), |&coord| self.neighbour(coord, direction));
 
-        let left_up: u8 = if bottom_right_char == 'M' && top_left_char == 'S' {
-            1
-        } else {
-            0
-        };
-
-        right_down + right_up + left_down + left_up >= 2
+                (direction, cells.collect())
+            })
+            .collect()
     }
 }
 
@@ -149,6 +299,13 @@ impl ops::Index<Coord> for Puzzle {
     type Output = char;
 
     fn index(&self, (x, y): Coord) -> &Self::Output {
+        assert!(
+            x < self.width() && y < self.height(),
+            "Coords ({}, {}) are outside the Puzzle",
+            x,
+            y
+        );
+
         &self.data[y][x]
     }
 }
@@ -161,7 +318,7 @@ pub struct PuzzleCoordIterator<'a> {
     next_y: usize,
 }
 
-impl<'a> Iterator for PuzzleCoordIterator<'a> {
+impl Iterator for PuzzleCoordIterator<'_> {
     type Item = Coord;
 
     fn next(&mut self) -> Option<Self::Item> {
@@ -0,0 +1,107 @@
+use std::collections::HashMap;
+
+use crate::puzzle::{Coord, Puzzle};
+
+/// ANSI foreground colours for the matches: red, green, yellow, blue,
+/// magenta and cyan.
+const COLOURS: [u8; 6] = [31, 32, 33, 34, 35, 36];
+
+/// Draw the Puzzle with only the letters that are part of a match kept, and
+/// `.` in place of every other letter. Each match is given as the coords of
+/// its letters.
+///
+/// With `colour`, the letters of each match are drawn in an ANSI colour of
+/// their own, cycling through six colours. Letters in more than one match
+/// take the colour of the first.
+pub fn render(puzzle: &Puzzle, matches: &[Vec<Coord>], colour: bool) -> String {
+    let mut owners: HashMap<Coord, usize> = HashMap::new();
+
+    for (idx, coords) in matches.iter().enumerate() {
+        for &coord in coords.iter() {
+            owners.entry(coord).or_insert(idx);
+        }
+    }
+
+    let rows: Vec<String> = (0..puzzle.height())
+        .map(|y| {
+            (0..puzzle.width())
+                .map(|x| match owners.get(&(x, y)) {
+                    None => ".".to_string(),
+                    Some(_) if !colour => puzzle[(x, y)].to_string(),
+                    Some(idx) => format!(
+                        "\x1b[{}m{}\x1b[0m",
+                        COLOURS[idx % COLOURS.len()],
+                        puzzle[(x, y)]
+                    ),
+                })
+                .collect()
+        })
+        .collect();
+
+    rows.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_input, puzzle::Direction, stencil::Stencil, tests::INPUT};
+
+    #[test]
+    fn test_render_words() {
+        let puzzle = parse_input(INPUT);
+        let matches: Vec<Vec<Coord>> = puzzle
+            .find_word("XMAS", &Direction::ALL)
+            .iter()
+            .map(|m| m.coords())
+            .collect();
+
+        // As drawn in the puzzle description
+        let expected = "....XXMAS.
+.SAMXMS...
+...S..A...
+..A.A.MS.X
+XMASAMX.MM
+X.....XA.A
+S.S.S.S.SS
+.A.A.A.A.A
+..M.M.M.MM
+.X.X.XMASX";
+
+        assert_eq!(render(&puzzle, &matches, false), expected);
+    }
+
+    #[test]
+    fn test_render_stencils() {
+        let puzzle = parse_input(INPUT);
+        let matches: Vec<Vec<Coord>> = puzzle
+            .find_stencil(&Stencil::from("M.S/.A./M.S"))
+            .iter()
+            .map(|m| m.coords())
+            .collect();
+
+        let expected = ".M.S......
+..A..MSMS.
+.M.S.MAA..
+..A.ASMSM.
+.M.S.M....
+..........
+S.S.S.S.S.
+.A.A.A.A..
+M.M.M.M.M.
+..........";
+
+        assert_eq!(render(&puzzle, &matches, false), expected);
+    }
+
+    #[test]
+    fn test_render_in_colour() {
+        let puzzle = parse_input("XMAS\nXMAS");
+        let matches = vec![vec![(0, 0), (1, 0)], vec![(1, 0), (1, 1)]];
+
+        // The shared M keeps the first match's colour
+        assert_eq!(
+            render(&puzzle, &matches, true),
+            "\x1b[31mX\x1b[0m\x1b[31mM\x1b[0m..\n.\x1b[32mM\x1b[0m.."
+        );
+    }
+}
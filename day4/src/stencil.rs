@@ -0,0 +1,225 @@
+use std::fmt;
+
+use crate::puzzle::{Coord, Puzzle};
+
+const WILDCARD: char = '.';
+
+/// A small 2D pattern to find in the Puzzle, where `.` matches any character.
+/// For example, an X made of two "MAS":
+///
+/// ```text
+/// M.S
+/// .A.
+/// M.S
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Stencil {
+    rows: Vec<Vec<Option<char>>>,
+}
+
+/// A stencil found in the Puzzle.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StencilMatch {
+    /// The Puzzle coords of the top left of the stencil
+    pub top_left: Coord,
+
+    /// The rotation or reflection of the stencil that matched
+    pub variant: Stencil,
+}
+
+/// Parse a stencil from rows separated by new lines or `/`, e.g. `M.S/.A./M.S`.
+impl From<&str> for Stencil {
+    fn from(input: &str) -> Self {
+        let rows: Vec<Vec<Option<char>>> = input
+            .split(['\n', '/'])
+            .map(str::trim)
+            .filter(|row| !row.is_empty())
+            .map(|row| {
+                row.chars()
+                    .map(|c| if c == WILDCARD { None } else { Some(c) })
+                    .collect()
+            })
+            .collect();
+
+        assert!(!rows.is_empty(), "Empty stencil");
+        assert!(
+            rows.iter().all(|row| row.len() == rows[0].len()),
+            "Stencil rows must all be the same length"
+        );
+
+        Stencil { rows }
+    }
+}
+
+impl fmt::Display for Stencil {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let rows: Vec<String> = self
+            .rows
+            .iter()
+            .map(|row| row.iter().map(|c| c.unwrap_or(WILDCARD)).collect())
+            .collect();
+
+        write!(f, "{}", rows.join("/"))
+    }
+}
+
+impl Stencil {
+    pub fn width(&self) -> usize {
+        self.rows[0].len()
+    }
+
+    pub fn height(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// The stencil turned a quarter turn clockwise.
+    pub fn rotated(&self) -> Stencil {
+        let rows = (0..self.width())
+            .map(|x| (0..self.height()).rev().map(|y| self.rows[y][x]).collect())
+            .collect();
+
+        Stencil { rows }
+    }
+
+    /// The stencil flipped left to right.
+    pub fn reflected(&self) -> Stencil {
+        let rows = self
+            .rows
+            .iter()
+            .map(|row| row.iter().rev().copied().collect())
+            .collect();
+
+        Stencil { rows }
+    }
+
+    /// Every distinct rotation and reflection of the stencil, starting with
+    /// the stencil itself.
+    pub fn variants(&self) -> Vec<Stencil> {
+        let mut variants: Vec<Stencil> = Vec::new();
+        let mut current = self.clone();
+
+        for _ in 0..4 {
+            for variant in [current.clone(), current.reflected()] {
+                if !variants.contains(&variant) {
+                    variants.push(variant);
+                }
+            }
+
+            current = current.rotated();
+        }
+
+        variants
+    }
+
+    /// Whether the stencil matches the Puzzle with its top left at the coords.
+    fn matches_at(&self, puzzle: &Puzzle, (left, top): Coord) -> bool {
+        if left + self.width() > puzzle.width() || top + self.height() > puzzle.height() {
+            return false;
+        }
+
+        self.rows.iter().enumerate().all(|(y, row)| {
+            row.iter()
+                .enumerate()
+                .all(|(x, c)| c.is_none_or(|c| puzzle[(left + x, top + y)] == c))
+        })
+    }
+}
+
+impl StencilMatch {
+    /// The coords of each letter the stencil matched, row by row. Wildcards
+    /// aren't included.
+    pub fn coords(&self) -> Vec<Coord> {
+        let (left, top) = self.top_left;
+
+        self.variant
+            .rows
+            .iter()
+            .enumerate()
+            .flat_map(|(y, row)| {
+                row.iter()
+                    .enumerate()
+                    .filter(|(_, c)| c.is_some())
+                    .map(move |(x, _)| (left + x, top + y))
+            })
+            .collect()
+    }
+}
+
+impl Puzzle {
+    /// Find every position where any rotation or reflection of the stencil
+    /// matches, ordered row by row. Different variants matching at the same
+    /// position are separate matches.
+    pub fn find_stencil(&self, stencil: &Stencil) -> Vec<StencilMatch> {
+        let variants = stencil.variants();
+
+        self.iter_coords()
+            .flat_map(|coord| {
+                variants
+                    .iter()
+                    .filter(move |variant| variant.matches_at(self, coord))
+                    .map(move |variant| StencilMatch {
+                        top_left: coord,
+                        variant: variant.clone(),
+                    })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_input;
+
+    #[test]
+    fn test_parse_and_display() {
+        let stencil = Stencil::from("M.S\n.A.\nM.S");
+
+        assert_eq!(stencil, Stencil::from("M.S/.A./M.S"));
+        assert_eq!(stencil.to_string(), "M.S/.A./M.S");
+        assert_eq!((stencil.width(), stencil.height()), (3, 3));
+    }
+
+    #[test]
+    fn test_rotated_and_reflected() {
+        let stencil = Stencil::from("AB/C.");
+
+        assert_eq!(stencil.rotated().to_string(), "CA/.B");
+        assert_eq!(stencil.reflected().to_string(), "BA/.C");
+
+        // Rotating changes the width and height
+        assert_eq!(Stencil::from("XMAS").rotated().to_string(), "X/M/A/S");
+    }
+
+    #[test]
+    fn test_variants() {
+        assert_eq!(Stencil::from("M.S/.A./M.S").variants().len(), 4);
+        assert_eq!(Stencil::from("XMAS").variants().len(), 4);
+        assert_eq!(Stencil::from("AB/C.").variants().len(), 8);
+        assert_eq!(Stencil::from("A").variants().len(), 1);
+    }
+
+    #[test]
+    fn test_find_stencil() {
+        let puzzle = parse_input("M.M.\n.A..\nS.S.\n....");
+        let matches = puzzle.find_stencil(&Stencil::from("M.S/.A./M.S"));
+
+        assert_eq!(
+            matches,
+            vec![StencilMatch {
+                top_left: (0, 0),
+                variant: Stencil::from("M.M/.A./S.S"),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_find_stencil_at_the_edges() {
+        let puzzle = parse_input("XMAS\nM...\nA...\nS...");
+
+        // Straight lines are stencils too
+        let matches = puzzle.find_stencil(&Stencil::from("XMAS"));
+        assert_eq!(matches.len(), 2);
+        assert!(matches.iter().all(|m| m.top_left == (0, 0)));
+    }
+}
@@ -0,0 +1,355 @@
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+
+use crate::{rules::RuleIndex, topo::topo_sort, PageRule};
+
+/// The rules as a whole, as a graph with an edge from each page to every
+/// page that must come after it.
+pub struct RuleGraph {
+    /// Every page mentioned by a rule, in ascending order
+    pages: Vec<usize>,
+
+    rules: Vec<PageRule>,
+    successors: HashMap<usize, BTreeSet<usize>>,
+}
+
+impl RuleGraph {
+    pub fn new(index: &RuleIndex) -> Self {
+        let rules = index.rules();
+
+        let mut pages: Vec<usize> = rules.iter().flat_map(|&(a, b)| [a, b]).collect();
+        pages.sort();
+        pages.dedup();
+
+        let mut successors: HashMap<usize, BTreeSet<usize>> = HashMap::new();
+        for &(before, after) in rules.iter() {
+            successors.entry(before).or_default().insert(after);
+        }
+
+        RuleGraph {
+            pages,
+            rules,
+            successors,
+        }
+    }
+
+    pub fn pages(&self) -> &[usize] {
+        &self.pages
+    }
+
+    fn successors(&self, page: usize) -> impl Iterator<Item = usize> + '_ {
+        self.successors.get(&page).into_iter().flatten().copied()
+    }
+
+    /// Pairs of pages with rules in both directions (`a|b` and `b|a`), with
+    /// the smaller page first.
+    pub fn contradictions(&self) -> Vec<PageRule> {
+        self.rules
+            .iter()
+            .filter(|&&(a, b)| a < b && self.successors(b).any(|page| page == a))
+            .copied()
+            .collect()
+    }
+
+    /// The strongly connected components of the graph: groups of pages where
+    /// every page must come both before and after every other, directly or
+    /// through other pages. Pages are sorted within each component, and
+    /// components are sorted by their smallest page.
+    pub fn components(&self) -> Vec<Vec<usize>> {
+        let mut tarjan = Tarjan {
+            graph: self,
+            index: HashMap::new(),
+            low_link: HashMap::new(),
+            stack: Vec::new(),
+            on_stack: HashSet::new(),
+            components: Vec::new(),
+        };
+
+        for &page in self.pages.iter() {
+            if !tarjan.index.contains_key(&page) {
+                tarjan.visit(page);
+            }
+        }
+
+        let mut components = tarjan.components;
+        for component in components.iter_mut() {
+            component.sort();
+        }
+        components.sort();
+
+        components
+    }
+
+    /// One cycle through each component with more than one page, or with a
+    /// page that must come before itself (`a|a`), starting at its smallest
+    /// page. Each page must come before the next, and the last before the
+    /// first.
+    pub fn cycles(&self) -> Vec<Vec<usize>> {
+        self.components()
+            .into_iter()
+            .filter(|component| {
+                component.len() > 1
+                    || self
+                        .successors(component[0])
+                        .any(|page| page == component[0])
+            })
+            .map(|component| self.shortest_cycle(&component))
+            .collect()
+    }
+
+    /// Rules that are implied by other rules, e.g. `a|c` when there are
+    /// already `a|b` and `b|c` rules.
+    ///
+    /// Within a cycle every page already comes before every other, so that
+    /// would make every rule in it redundant, even though removing them all
+    /// would break the cycle. Instead the rules are compared between whole
+    /// components: a rule is redundant when there is another way from its
+    /// `before` page's component to its `after` page's component. Rules
+    /// within a component never are.
+    pub fn redundant_rules(&self) -> Vec<PageRule> {
+        let component_of: HashMap<usize, usize> = self
+            .components()
+            .into_iter()
+            .enumerate()
+            .flat_map(|(idx, component)| component.into_iter().map(move |page| (page, idx)))
+            .collect();
+
+        // The condensation of the graph, with an edge between components
+        // wherever a rule goes from one to another
+        let mut condensed: HashMap<usize, BTreeSet<usize>> = HashMap::new();
+        for &(before, after) in self.rules.iter() {
+            if component_of[&before] != component_of[&after] {
+                condensed
+                    .entry(component_of[&before])
+                    .or_default()
+                    .insert(component_of[&after]);
+            }
+        }
+
+        let successors =
+            |component: usize| condensed.get(&component).into_iter().flatten().copied();
+
+        self.rules
+            .iter()
+            .filter(|&&(before, after)| {
+                let (from, to) = (component_of[&before], component_of[&after]);
+                if from == to {
+                    return false;
+                }
+
+                // Look for another way from `from` to `to`
+                let mut seen: HashSet<usize> = HashSet::from([from]);
+                let mut queue: VecDeque<usize> = successors(from)
+                    .filter(|&component| component != to)
+                    .collect();
+
+                while let Some(component) = queue.pop_front() {
+                    if component == to {
+                        return true;
+                    }
+
+                    if seen.insert(component) {
+                        queue.extend(successors(component));
+                    }
+                }
+
+                false
+            })
+            .copied()
+            .collect()
+    }
+
+    /// Whether the rules put every page in exactly one order. Returns the
+    /// order if they do.
+    pub fn total_order(&self, index: &RuleIndex) -> Option<Vec<usize>> {
+        topo_sort(&self.pages, index)
+            .ok()
+            .filter(|order| order.ambiguous.is_empty())
+            .map(|order| order.pages)
+    }
+
+    /// Draw the graph in Graphviz DOT format. Contradictory rules are drawn in
+    /// red and redundant rules are dashed.
+    pub fn to_dot(&self) -> String {
+        let contradictions: HashSet<PageRule> = self
+            .contradictions()
+            .into_iter()
+            .flat_map(|(a, b)| [(a, b), (b, a)])
+            .collect();
+        let redundant: HashSet<PageRule> = self.redundant_rules().into_iter().collect();
+
+        let mut dot = String::from("digraph rules {\n");
+
+        for &page in self.pages.iter() {
+            dot.push_str(&format!("    {};\n", page));
+        }
+
+        for rule in self.rules.iter() {
+            let mut attributes = Vec::new();
+
+            if contradictions.contains(rule) {
+                attributes.push("color=red");
+            }
+            if redundant.contains(rule) {
+                attributes.push("style=dashed");
+            }
+
+            if attributes.is_empty() {
+                dot.push_str(&format!("    {} -> {};\n", rule.0, rule.1));
+            } else {
+                dot.push_str(&format!(
+                    "    {} -> {} [{}];\n",
+                    rule.0,
+                    rule.1,
+                    attributes.join(", ")
+                ));
+            }
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// The shortest cycle through the smallest page of a component, found by
+    /// a breadth-first search that stays inside the component.
+    fn shortest_cycle(&self, component: &[usize]) -> Vec<usize> {
+        let start = component[0];
+        let mut previous: HashMap<usize, usize> = HashMap::new();
+        let mut queue = VecDeque::from([start]);
+
+        while let Some(page) = queue.pop_front() {
+            for next in self.successors(page) {
+                if next == start {
+                    let mut cycle = vec![page];
+
+                    while let Some(&prev) = previous.get(cycle.last().expect("Empty cycle")) {
+                        cycle.push(prev);
+                    }
+
+                    cycle.reverse();
+                    return cycle;
+                }
+
+                if component.binary_search(&next).is_ok() && !previous.contains_key(&next) {
+                    previous.insert(next, page);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        unreachable!("Component {:?} has no cycle", component)
+    }
+}
+
+/// Tarjan's strongly connected components algorithm.
+struct Tarjan<'a> {
+    graph: &'a RuleGraph,
+    index: HashMap<usize, usize>,
+    low_link: HashMap<usize, usize>,
+    stack: Vec<usize>,
+    on_stack: HashSet<usize>,
+    components: Vec<Vec<usize>>,
+}
+
+impl Tarjan<'_> {
+    fn visit(&mut self, page: usize) {
+        let index = self.index.len();
+        self.index.insert(page, index);
+        self.low_link.insert(page, index);
+        self.stack.push(page);
+        self.on_stack.insert(page);
+
+        for next in self.graph.successors(page) {
+            if !self.index.contains_key(&next) {
+                self.visit(next);
+                let low = self.low_link[&page].min(self.low_link[&next]);
+                self.low_link.insert(page, low);
+            } else if self.on_stack.contains(&next) {
+                let low = self.low_link[&page].min(self.index[&next]);
+                self.low_link.insert(page, low);
+            }
+        }
+
+        if self.low_link[&page] == self.index[&page] {
+            let mut component = Vec::new();
+
+            loop {
+                let member = self.stack.pop().expect("Component missing from stack");
+                self.on_stack.remove(&member);
+                component.push(member);
+
+                if member == page {
+                    break;
+                }
+            }
+
+            self.components.push(component);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_acyclic_total_order() {
+        let index = RuleIndex::from_iter([(1, 2), (2, 3), (1, 3)]);
+        let graph = RuleGraph::new(&index);
+
+        assert_eq!(graph.pages(), &[1, 2, 3]);
+        assert_eq!(graph.contradictions(), vec![]);
+        assert_eq!(graph.components(), vec![vec![1], vec![2], vec![3]]);
+        assert_eq!(graph.cycles(), Vec::<Vec<usize>>::new());
+        assert_eq!(graph.redundant_rules(), vec![(1, 3)]);
+        assert_eq!(graph.total_order(&index), Some(vec![1, 2, 3]));
+    }
+
+    #[test]
+    fn test_partial_order() {
+        let index = RuleIndex::from_iter([(1, 3), (2, 3)]);
+        let graph = RuleGraph::new(&index);
+
+        assert_eq!(graph.redundant_rules(), vec![]);
+        assert_eq!(graph.total_order(&index), None);
+    }
+
+    #[test]
+    fn test_contradictions_and_cycles() {
+        let index = RuleIndex::from_iter([(1, 2), (2, 1), (3, 4), (4, 5), (5, 3), (5, 6)]);
+        let graph = RuleGraph::new(&index);
+
+        assert_eq!(graph.contradictions(), vec![(1, 2)]);
+        assert_eq!(graph.components(), vec![vec![1, 2], vec![3, 4, 5], vec![6]]);
+        assert_eq!(graph.cycles(), vec![vec![1, 2], vec![3, 4, 5]]);
+        assert_eq!(graph.total_order(&index), None);
+    }
+
+    #[test]
+    fn test_rules_in_a_cycle_are_not_redundant() {
+        let index = RuleIndex::from_iter([(1, 2), (2, 3), (3, 1), (1, 4), (2, 4), (4, 5), (1, 5)]);
+        let graph = RuleGraph::new(&index);
+
+        // 1, 2 and 3 all come before each other, and before 4 in two ways,
+        // but only 1|5 is implied through another component
+        assert_eq!(graph.redundant_rules(), vec![(1, 5)]);
+    }
+
+    #[test]
+    fn test_self_rule_is_a_cycle() {
+        let index = RuleIndex::from_iter([(1, 1), (1, 2)]);
+        let graph = RuleGraph::new(&index);
+
+        assert_eq!(graph.cycles(), vec![vec![1]]);
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let index = RuleIndex::from_iter([(1, 2), (2, 1), (2, 3), (1, 3)]);
+        let dot = RuleGraph::new(&index).to_dot();
+
+        assert_eq!(
+            dot,
+            "digraph rules {\n    1;\n    2;\n    3;\n    1 -> 2 [color=red];\n    1 -> 3;\n    2 -> 1 [color=red];\n    2 -> 3;\n}\n"
+        );
+    }
+}
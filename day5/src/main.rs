@@ -1,8 +1,54 @@
-use std::cmp;
+mod analysis;
+mod precedence;
+mod repair;
+mod rules;
+mod topo;
+
+use std::{fs, io};
+
+use analysis::RuleGraph;
+use precedence::Precedence;
+use rules::RuleIndex;
+use shared::sections::{split_sections, ParseError};
+use topo::topo_sort;
 
 fn main() {
     let input = shared::read_file_from_args();
-    let puzzle = Puzzle::from(input.as_str());
+
+    let middle = match shared::flag_value("--middle") {
+        Some(policy) => MiddlePolicy::from(policy.as_str()),
+        None => MiddlePolicy::Reject,
+    };
+
+    let puzzle = Puzzle::parse(input.as_str(), middle).unwrap_or_else(|problems| {
+        panic!("Invalid input:\n{}", problems.join("\n"));
+    });
+
+    if shared::has_flag("--analyse") {
+        analyse(&puzzle);
+    }
+
+    if let Some(filename) = shared::flag_value("--dot") {
+        let dot = RuleGraph::new(&puzzle.rules).to_dot();
+        fs::write(filename, dot).expect("Failed to write DOT file");
+    }
+
+    if shared::has_flag("--query") {
+        query(&puzzle);
+        return;
+    }
+
+    if shared::has_flag("--check") {
+        check(&puzzle);
+    }
+
+    if shared::has_flag("--explain") {
+        explain(&puzzle);
+    }
+
+    if shared::has_flag("--repair") {
+        print_repairs(&puzzle);
+    }
 
     let part_one = part_one(&puzzle);
     println!("part one: {}", part_one);
@@ -11,12 +57,153 @@ fn main() {
     println!("part one: {}", part_two);
 }
 
+/// Answer precedence queries from stdin, one per line:
+///
+/// ```text
+/// before 47 53               (must 47 come before 53?)
+/// preceding 53               (which pages must come before 53?)
+/// position 53 in 75,47,61,53 (where could 53 go in this update?)
+/// ```
+fn query(puzzle: &Puzzle) {
+    let precedence = Precedence::new(&puzzle.rules);
+    let page = |page: &str| page.parse::<usize>().expect("Invalid page");
+
+    for line in io::stdin().lines() {
+        let line = line.expect("Failed to read query");
+        let pieces: Vec<&str> = line.split_whitespace().collect();
+
+        match pieces.as_slice() {
+            ["before", a, b] => println!("{}", precedence.must_precede(page(a), page(b))),
+            ["preceding", x] => println!("{:?}", precedence.predecessors(page(x))),
+            ["position", x, "in", pages] => {
+                let pages: Vec<usize> = pages.split(',').map(page).collect();
+
+                match Precedence::for_update(&puzzle.rules, &pages).legal_positions(page(x)) {
+                    Some(positions) => {
+                        println!("{} to {}", positions.start() + 1, positions.end() + 1)
+                    }
+                    None => println!("no legal position"),
+                }
+            }
+            [] => {}
+            _ => println!("Unknown query: {}", line),
+        }
+    }
+}
+
+/// Report on the rules as a whole, rather than one update at a time.
+fn analyse(puzzle: &Puzzle) {
+    let graph = RuleGraph::new(&puzzle.rules);
+    let components = graph.components();
+
+    println!("pages: {}", graph.pages().len());
+    println!("contradictions: {:?}", graph.contradictions());
+    println!("cycles: {:?}", graph.cycles());
+    println!(
+        "strongly connected components: {} ({} with more than one page)",
+        components.len(),
+        components.iter().filter(|c| c.len() > 1).count()
+    );
+    println!("redundant rules: {:?}", graph.redundant_rules());
+
+    match graph.total_order(&puzzle.rules) {
+        Some(order) => println!("total order: {:?}", order),
+        None => println!("total order: none"),
+    }
+}
+
+/// Report any updates whose rules form a cycle, or don't decide the order of
+/// every page.
+fn check(puzzle: &Puzzle) {
+    for (idx, update) in puzzle.updates.iter().enumerate() {
+        match topo_sort(&update.pages, &puzzle.rules) {
+            Err(cycle) => println!("update {}: rules form a cycle {:?}", idx + 1, cycle.pages),
+            Ok(order) if !order.ambiguous.is_empty() => println!(
+                "update {}: no rules order the pages in {:?}",
+                idx + 1,
+                order.ambiguous
+            ),
+            Ok(_) => {}
+        }
+    }
+}
+
+/// Explain why each rejected update was rejected, listing every rule it
+/// breaks and where the pages involved are.
+fn explain(puzzle: &Puzzle) {
+    for (idx, update) in puzzle.updates.iter().enumerate() {
+        let violations = puzzle.rules.violations(&update.pages);
+
+        if violations.is_empty() {
+            continue;
+        }
+
+        println!("update {} {:?} breaks:", idx + 1, update.pages);
+
+        for violation in violations {
+            let (before, after) = violation.rule;
+            let (before_idx, after_idx) = violation.positions;
+
+            println!(
+                "  {}|{}: {} is at position {}, after {} at position {}",
+                before,
+                after,
+                before,
+                before_idx + 1,
+                after,
+                after_idx + 1
+            );
+        }
+    }
+}
+
+/// Repair each invalid update with as few page moves as possible, comparing
+/// the middle pages with those from fully sorting the update.
+fn print_repairs(puzzle: &Puzzle) {
+    let mut sorted_total = 0;
+    let mut repaired_total = 0;
+
+    for (idx, update) in puzzle.updates.iter().enumerate() {
+        if update.is_valid(&puzzle.rules) {
+            continue;
+        }
+
+        let sorted = update.clone_and_sort(&puzzle.rules);
+        let repair = repair::repair(&update.pages, &puzzle.rules)
+            .unwrap_or_else(|cycle| panic!("Rules form a cycle: {:?}", cycle.pages));
+        let repaired = Update::from(&repair.pages);
+
+        println!(
+            "update {}: {} moves, sorted middle {}, repaired middle {}",
+            idx + 1,
+            repair.moves.len(),
+            puzzle.middle_value(&sorted),
+            puzzle.middle_value(&repaired)
+        );
+
+        for m in repair.moves.iter() {
+            match m.after {
+                Some(after) => println!("  move {} after {}", m.page, after),
+                None => println!("  move {} to the front", m.page),
+            }
+        }
+
+        sorted_total += puzzle.middle_value(&sorted);
+        repaired_total += puzzle.middle_value(&repaired);
+    }
+
+    println!(
+        "sorted middle pages: {}, repaired middle pages: {}",
+        sorted_total, repaired_total
+    );
+}
+
 fn part_one(puzzle: &Puzzle) -> usize {
     puzzle
         .updates
         .iter()
         .filter(|update| update.is_valid(&puzzle.rules))
-        .map(middle_value)
+        .map(|update| puzzle.middle_value(update))
         .sum()
 }
 
@@ -26,54 +213,160 @@ fn part_two(puzzle: &Puzzle) -> usize {
         .iter()
         .filter(|update| !update.is_valid(&puzzle.rules))
         .map(|update| update.clone_and_sort(&puzzle.rules))
-        .map(|update| middle_value(&update))
+        .map(|update| puzzle.middle_value(&update))
         .sum()
 }
 
-fn middle_value(update: &Update) -> usize {
-    update.pages[(update.pages.len() - 1) / 2]
+/// What to do about updates with an even number of pages, which have two
+/// middle pages.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum MiddlePolicy {
+    /// Refuse to parse the input
+    Reject,
+
+    /// Use the first of the two middle pages
+    Lower,
+
+    /// Use the second of the two middle pages
+    Upper,
+
+    /// Use the average of the two middle pages, rounded down
+    Average,
+}
+
+impl From<&str> for MiddlePolicy {
+    fn from(policy: &str) -> Self {
+        match policy {
+            "reject" => MiddlePolicy::Reject,
+            "lower" => MiddlePolicy::Lower,
+            "upper" => MiddlePolicy::Upper,
+            "average" => MiddlePolicy::Average,
+            _ => panic!("Invalid middle page policy: {}", policy),
+        }
+    }
+}
+
+impl MiddlePolicy {
+    /// The middle page of some pages, if there is one.
+    fn middle_value(&self, pages: &[usize]) -> Option<usize> {
+        let len = pages.len();
+
+        if len % 2 == 1 {
+            return Some(pages[len / 2]);
+        }
+
+        let (lower, upper) = (pages.get((len / 2).checked_sub(1)?)?, pages.get(len / 2)?);
+
+        match self {
+            MiddlePolicy::Reject => None,
+            MiddlePolicy::Lower => Some(*lower),
+            MiddlePolicy::Upper => Some(*upper),
+            MiddlePolicy::Average => Some((lower + upper) / 2),
+        }
+    }
 }
 
 #[derive(Debug)]
 struct Puzzle {
-    rules: Vec<PageRule>,
+    rules: RuleIndex,
     updates: Vec<Update>,
+    middle: MiddlePolicy,
 }
 
-impl From<&str> for Puzzle {
-    fn from(input: &str) -> Self {
-        let pieces = input.trim().split_once("\n\n").expect("Invalid input");
-
-        let rules: Vec<(usize, usize)> = pieces
-            .0
-            .split("\n")
-            .map(|rule| {
-                let parts = rule.trim().split_once("|").expect("Invalid rule");
-                let part_one = parts.0.parse::<usize>().expect("Invalid rule");
-                let part_two = parts.1.parse::<usize>().expect("Invalid rule");
-                (part_one, part_two)
-            })
-            .collect();
+impl Puzzle {
+    /// Parse the input, checking every update has a middle page under the
+    /// policy. Returns a description of each update without one.
+    fn parse(input: &str, middle: MiddlePolicy) -> Result<Puzzle, Vec<String>> {
+        let mut puzzle = parse_input(input).map_err(|err| vec![err.to_string()])?;
+        puzzle.middle = middle;
 
-        let updates = pieces
-            .1
-            .trim()
-            .lines()
-            .map(|line| {
-                let pages = line
-                    .trim()
-                    .split(',')
-                    .map(|page| page.parse().expect("Invalid page"))
-                    .collect();
-
-                Update { pages }
+        let problems: Vec<String> = puzzle
+            .updates
+            .iter()
+            .enumerate()
+            .filter(|(_, update)| middle.middle_value(&update.pages).is_none())
+            .map(|(idx, update)| match update.pages.len() {
+                0 => format!("update {}: no pages", idx + 1),
+                len => format!(
+                    "update {}: {} pages, so no single middle page {:?}",
+                    idx + 1,
+                    len,
+                    update.pages
+                ),
             })
             .collect();
 
-        Puzzle { rules, updates }
+        if problems.is_empty() {
+            Ok(puzzle)
+        } else {
+            Err(problems)
+        }
+    }
+
+    /// The middle page of an update.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the update has no middle page under the Puzzle's policy,
+    /// which `parse` rules out.
+    fn middle_value(&self, update: &Update) -> usize {
+        self.middle
+            .middle_value(&update.pages)
+            .expect("Update has no middle page")
+    }
+}
+
+/// Parse the input, rejecting updates without a single middle page.
+impl From<&str> for Puzzle {
+    fn from(input: &str) -> Self {
+        Puzzle::parse(input, MiddlePolicy::Reject)
+            .unwrap_or_else(|problems| panic!("Invalid input: {}", problems.join("; ")))
     }
 }
 
+/// Parse the rules and updates sections of the input, without checking the
+/// updates have middle pages.
+fn parse_input(input: &str) -> Result<Puzzle, ParseError> {
+    let sections = split_sections(input, 2)?;
+
+    let rules: RuleIndex = sections[0]
+        .parse_lines(|rule| {
+            let (before, after) = rule
+                .split_once('|')
+                .ok_or_else(|| format!("Invalid rule {:?}", rule))?;
+            let page = |page: &str| {
+                page.trim()
+                    .parse::<usize>()
+                    .map_err(|_| format!("Invalid page {:?} in rule {:?}", page, rule))
+            };
+
+            Ok((page(before)?, page(after)?))
+        })?
+        .into_iter()
+        .collect();
+
+    let updates = sections[1].parse_lines(|line| {
+        // A blank line is an update without any pages
+        let pages = line
+            .split(',')
+            .map(str::trim)
+            .filter(|page| !page.is_empty())
+            .map(|page| {
+                page.parse()
+                    .map_err(|_| format!("Invalid page {:?} in update {:?}", page, line))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Update { pages })
+    })?;
+
+    Ok(Puzzle {
+        rules,
+        updates,
+        middle: MiddlePolicy::Reject,
+    })
+}
+
 type PageRule = (usize, usize);
 
 #[derive(Clone, Debug)]
@@ -82,32 +375,21 @@ struct Update {
 }
 
 impl Update {
-    fn is_valid(&self, rules: &[PageRule]) -> bool {
-        self.pages.iter().enumerate().all(|(page_idx, &page)| {
-            rules.iter().filter(|rule| rule.0 == page).all(|rule| {
-                self.pages
-                    .iter()
-                    .position(|&n| n == rule.1)
-                    .map(|idx| page_idx < idx)
-                    .unwrap_or(true)
-            })
-        })
+    fn is_valid(&self, rules: &RuleIndex) -> bool {
+        rules.violations(&self.pages).is_empty()
     }
 
-    fn clone_and_sort(&self, rules: &[PageRule]) -> Update {
-        let mut pages = self.pages.clone();
+    /// Sort the pages to follow the rules. Pages the rules don't order
+    /// keep their original order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the rules between the pages form a cycle.
+    fn clone_and_sort(&self, rules: &RuleIndex) -> Update {
+        let order = topo_sort(&self.pages, rules)
+            .unwrap_or_else(|cycle| panic!("Rules form a cycle: {:?}", cycle.pages));
 
-        pages.sort_by(|&a, &b| {
-            // Find a rule that applies to the pages being compared
-            let rule = rules.iter().find(|rule| rule.0 == a && rule.1 == b);
-
-            match rule {
-                Some(_) => cmp::Ordering::Less,
-                None => cmp::Ordering::Equal,
-            }
-        });
-
-        Update { pages }
+        Update { pages: order.pages }
     }
 }
 
@@ -120,7 +402,6 @@ impl<'a> From<&'a Vec<usize>> for Update {
 }
 
 #[cfg(test)]
-
 mod tests {
     use super::*;
 
@@ -155,6 +436,17 @@ mod tests {
         97,13,75,29,47
     "#;
 
+    /// The rules from the example input.
+    pub(crate) fn example_rules() -> RuleIndex {
+        Puzzle::from(INPUT).rules
+    }
+
+    #[test]
+    #[should_panic(expected = "update 2: 2 pages, so no single middle page [1, 2]")]
+    fn test_from_rejects_updates_without_a_middle_page() {
+        let _ = Puzzle::from("1|2\n\n1,2,3\n1,2\n");
+    }
+
     #[test]
     fn test_part_one() {
         let puzzle = Puzzle::from(INPUT);
@@ -170,4 +462,128 @@ mod tests {
 
         assert_eq!(result, 123);
     }
+
+    #[test]
+    fn test_clone_and_sort_without_complete_rules() {
+        // 1|3 and 3|2 only say anything about 2 and 1 through 3
+        let rules = RuleIndex::from_iter([(1, 3), (3, 2)]);
+        let update = Update::from(&vec![2, 3, 1]);
+
+        assert_eq!(update.clone_and_sort(&rules).pages, vec![1, 3, 2]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Rules form a cycle")]
+    fn test_clone_and_sort_with_cycle() {
+        let rules = RuleIndex::from_iter([(1, 2), (2, 1)]);
+        Update::from(&vec![1, 2]).clone_and_sort(&rules);
+    }
+
+    const VARIANT_INPUT: &str = r#"
+        1|2
+        2|3
+
+        1,2,3
+        3,1,4,2
+
+        2,1
+    "#;
+
+    #[test]
+    fn test_middle_value_policies() {
+        let pages = [10, 20, 35, 40];
+
+        assert_eq!(MiddlePolicy::Reject.middle_value(&pages), None);
+        assert_eq!(MiddlePolicy::Lower.middle_value(&pages), Some(20));
+        assert_eq!(MiddlePolicy::Upper.middle_value(&pages), Some(35));
+        assert_eq!(MiddlePolicy::Average.middle_value(&pages), Some(27));
+
+        // Odd lengths have one middle page whatever the policy
+        assert_eq!(MiddlePolicy::Reject.middle_value(&[1, 2, 3]), Some(2));
+        assert_eq!(MiddlePolicy::Average.middle_value(&[1, 2, 3]), Some(2));
+    }
+
+    #[test]
+    fn test_middle_value_of_empty_update() {
+        for policy in ["reject", "lower", "upper", "average"] {
+            assert_eq!(MiddlePolicy::from(policy).middle_value(&[]), None);
+        }
+    }
+
+    #[test]
+    fn test_parse_reports_updates_without_middle() {
+        let problems = Puzzle::parse(VARIANT_INPUT, MiddlePolicy::Reject).unwrap_err();
+
+        assert_eq!(
+            problems,
+            vec![
+                "update 2: 4 pages, so no single middle page [3, 1, 4, 2]",
+                "update 3: no pages",
+                "update 4: 2 pages, so no single middle page [2, 1]",
+            ]
+        );
+
+        // Empty updates never have a middle page
+        let problems = Puzzle::parse(VARIANT_INPUT, MiddlePolicy::Lower).unwrap_err();
+        assert_eq!(problems, vec!["update 3: no pages"]);
+    }
+
+    #[test]
+    fn test_parts_with_even_length_updates() {
+        let input = VARIANT_INPUT.replace("\n\n        2,1", "\n        2,1");
+
+        // The invalid updates sort to 1,4,2,3 and 1,2
+        let puzzle = Puzzle::parse(&input, MiddlePolicy::Lower).unwrap();
+        assert_eq!(part_one(&puzzle), 2);
+        assert_eq!(part_two(&puzzle), 4 + 1);
+
+        let puzzle = Puzzle::parse(&input, MiddlePolicy::Upper).unwrap();
+        assert_eq!(part_two(&puzzle), 2 + 2);
+
+        let puzzle = Puzzle::parse(&input, MiddlePolicy::Average).unwrap();
+        assert_eq!(part_two(&puzzle), 3 + 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid middle page policy")]
+    fn test_invalid_middle_policy() {
+        let _ = MiddlePolicy::from("median");
+    }
+
+    #[test]
+    fn test_parse_windows_line_endings_and_comments() {
+        let input = INPUT
+            .lines()
+            .map(str::trim)
+            .collect::<Vec<_>>()
+            .join("\r\n")
+            .replacen("\r\n\r\n", "\r\n\r\n\r\n# Updates\r\n", 1);
+
+        let puzzle = Puzzle::from(format!("# Rules\r\n{}", input).as_str());
+
+        assert_eq!(puzzle.updates.len(), 6);
+        assert_eq!(part_one(&puzzle), 143);
+        assert_eq!(part_two(&puzzle), 123);
+    }
+
+    #[test]
+    fn test_parse_reports_section_and_line() {
+        let problems = Puzzle::parse("1|2\n2|x\n\n1,2,3", MiddlePolicy::Reject).unwrap_err();
+        assert_eq!(
+            problems,
+            vec!["section 1, line 2: Invalid page \"x\" in rule \"2|x\""]
+        );
+
+        let problems = Puzzle::parse("1|2\n\n\n1,2,3\n1;2", MiddlePolicy::Reject).unwrap_err();
+        assert_eq!(
+            problems,
+            vec!["section 2, line 5: Invalid page \"1;2\" in update \"1;2\""]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected 2 sections, found 1")]
+    fn test_parse_missing_updates() {
+        let _ = Puzzle::from("1|2\n2|3\n");
+    }
 }
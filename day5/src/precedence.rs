@@ -0,0 +1,181 @@
+use std::{collections::HashMap, ops::RangeInclusive};
+
+use crate::rules::RuleIndex;
+
+/// Which pages must come before which, directly or through other pages,
+/// precomputed so queries don't need to search the rules.
+///
+/// Rules only apply when both of their pages are in an update, so the pages
+/// that can link two others together depend on the update. `new` links
+/// through every page, and `for_update` only through an update's pages.
+pub struct Precedence {
+    pages: Vec<usize>,
+    indexes: HashMap<usize, usize>,
+
+    // A bitset for each page, of the pages that must come after it
+    after: Vec<Vec<u64>>,
+}
+
+impl Precedence {
+    /// Precedence over every page mentioned by the rules.
+    pub fn new(rules: &RuleIndex) -> Self {
+        let mut pages: Vec<usize> = rules
+            .rules()
+            .into_iter()
+            .flat_map(|(a, b)| [a, b])
+            .collect();
+        pages.sort();
+        pages.dedup();
+
+        Precedence::for_update(rules, &pages)
+    }
+
+    /// Precedence between an update's pages, using only the rules where both
+    /// pages are in the update.
+    pub fn for_update(rules: &RuleIndex, pages: &[usize]) -> Self {
+        let indexes: HashMap<usize, usize> = pages
+            .iter()
+            .enumerate()
+            .map(|(idx, &page)| (page, idx))
+            .collect();
+
+        let words = pages.len().div_ceil(64);
+        let mut after = vec![vec![0u64; words]; pages.len()];
+
+        for (idx, &page) in pages.iter().enumerate() {
+            for other in rules.after(page).filter_map(|other| indexes.get(&other)) {
+                after[idx][other / 64] |= 1 << (other % 64);
+            }
+        }
+
+        // Warshall's algorithm, a word at a time
+        for k in 0..pages.len() {
+            let through = after[k].clone();
+
+            for row in after.iter_mut() {
+                if row[k / 64] & (1 << (k % 64)) != 0 {
+                    for (word, via) in row.iter_mut().zip(through.iter()) {
+                        *word |= via;
+                    }
+                }
+            }
+        }
+
+        Precedence {
+            pages: pages.to_vec(),
+            indexes,
+            after,
+        }
+    }
+
+    /// Whether page `a` must come before page `b`. False if either page is
+    /// unknown.
+    pub fn must_precede(&self, a: usize, b: usize) -> bool {
+        match (self.indexes.get(&a), self.indexes.get(&b)) {
+            (Some(&a), Some(&b)) => self.after[a][b / 64] & (1 << (b % 64)) != 0,
+            _ => false,
+        }
+    }
+
+    /// The pages that must come before a page, in ascending order.
+    pub fn predecessors(&self, page: usize) -> Vec<usize> {
+        self.matching(|other| self.must_precede(other, page))
+    }
+
+    /// The pages that must come after a page, in ascending order.
+    pub fn successors(&self, page: usize) -> Vec<usize> {
+        self.matching(|other| self.must_precede(page, other))
+    }
+
+    /// The positions a page could take in a valid order of the pages, from
+    /// the number of pages that must come before it to the number that must
+    /// come after it. `None` if the page is unknown or is part of a cycle, in
+    /// which case it has no legal position.
+    pub fn legal_positions(&self, page: usize) -> Option<RangeInclusive<usize>> {
+        if !self.indexes.contains_key(&page) || self.must_precede(page, page) {
+            return None;
+        }
+
+        let earliest = self.predecessors(page).len();
+        let latest = self.pages.len() - 1 - self.successors(page).len();
+
+        Some(earliest..=latest)
+    }
+
+    fn matching(&self, predicate: impl Fn(usize) -> bool) -> Vec<usize> {
+        let mut pages: Vec<usize> = self
+            .pages
+            .iter()
+            .copied()
+            .filter(|&page| predicate(page))
+            .collect();
+
+        pages.sort();
+        pages
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_must_precede() {
+        let rules = RuleIndex::from_iter([(1, 2), (2, 3), (4, 3)]);
+        let precedence = Precedence::new(&rules);
+
+        assert!(precedence.must_precede(1, 2));
+        assert!(precedence.must_precede(1, 3));
+        assert!(!precedence.must_precede(3, 1));
+        assert!(!precedence.must_precede(1, 4));
+        assert!(!precedence.must_precede(1, 99));
+    }
+
+    #[test]
+    fn test_predecessors_and_successors() {
+        let rules = RuleIndex::from_iter([(1, 2), (2, 3), (4, 3)]);
+        let precedence = Precedence::new(&rules);
+
+        assert_eq!(precedence.predecessors(3), vec![1, 2, 4]);
+        assert_eq!(precedence.successors(1), vec![2, 3]);
+        assert_eq!(precedence.predecessors(1), vec![]);
+    }
+
+    #[test]
+    fn test_only_links_through_update_pages() {
+        let rules = RuleIndex::from_iter([(1, 2), (2, 3)]);
+
+        assert!(Precedence::new(&rules).must_precede(1, 3));
+        assert!(!Precedence::for_update(&rules, &[3, 1]).must_precede(1, 3));
+    }
+
+    #[test]
+    fn test_legal_positions() {
+        let rules = RuleIndex::from_iter([(1, 2), (2, 3), (4, 3)]);
+        let precedence = Precedence::for_update(&rules, &[3, 4, 2, 1, 5]);
+
+        assert_eq!(precedence.legal_positions(1), Some(0..=2));
+        assert_eq!(precedence.legal_positions(3), Some(3..=4));
+        assert_eq!(precedence.legal_positions(5), Some(0..=4));
+        assert_eq!(precedence.legal_positions(6), None);
+    }
+
+    #[test]
+    fn test_legal_positions_in_cycle() {
+        let rules = RuleIndex::from_iter([(1, 2), (2, 1), (1, 3)]);
+        let precedence = Precedence::for_update(&rules, &[1, 2, 3]);
+
+        assert_eq!(precedence.legal_positions(1), None);
+    }
+
+    #[test]
+    fn test_many_pages() {
+        // Spans more than one word of the bitsets
+        let rules: RuleIndex = (0..150).map(|page| (page, page + 1)).collect();
+        let precedence = Precedence::new(&rules);
+
+        assert!(precedence.must_precede(0, 150));
+        assert_eq!(precedence.predecessors(100).len(), 100);
+        assert_eq!(precedence.legal_positions(70), Some(70..=70));
+    }
+}
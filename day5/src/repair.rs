@@ -0,0 +1,239 @@
+use std::collections::HashMap;
+
+use crate::{
+    precedence::Precedence,
+    rules::RuleIndex,
+    topo::{topo_sort, Cycle},
+};
+
+/// Take a page out of the update and put it back straight after another page,
+/// or at the front if `after` is `None`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Move {
+    pub page: usize,
+    pub after: Option<usize>,
+}
+
+/// A valid ordering reached by moving as few pages as possible.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Repair {
+    pub pages: Vec<usize>,
+
+    /// The moves that turn the original pages into the repaired pages, in
+    /// the order they should be applied
+    pub moves: Vec<Move>,
+}
+
+/// Find the fewest page moves that make an update valid.
+///
+/// The pages that stay put must already be in an order the rules allow,
+/// including rules that only apply through other pages (e.g. `a|b` and `b|c`
+/// mean `a` must come before `c`). Two pages conflict if they are the wrong
+/// way round, and conflicts chain together (if `c` is wrongly before `b`,
+/// which is wrongly before `a`, then `c` is wrongly before `a`), so the
+/// largest conflict-free group can be found with a bipartite matching
+/// (Dilworth's theorem) instead of trying every group.
+pub fn repair(pages: &[usize], rules: &RuleIndex) -> Result<Repair, Cycle> {
+    // Bail out early on cycles, as no order can be valid
+    topo_sort(pages, rules)?;
+
+    let precedence = Precedence::for_update(rules, pages);
+    let conflicts = |i: usize, j: usize| i < j && precedence.must_precede(pages[j], pages[i]);
+
+    let keep = largest_conflict_free(pages.len(), conflicts);
+
+    // Sort the pages again, with extra rules to keep the kept pages in their
+    // current order
+    let kept: Vec<usize> = (0..pages.len())
+        .filter(|&idx| keep[idx])
+        .map(|idx| pages[idx])
+        .collect();
+
+    let restricted: RuleIndex = pages
+        .iter()
+        .flat_map(|&page| {
+            rules
+                .after(page)
+                .filter(|after| pages.contains(after))
+                .map(move |after| (page, after))
+        })
+        .chain(kept.windows(2).map(|pair| (pair[0], pair[1])))
+        .collect();
+
+    let repaired = topo_sort(pages, &restricted)?.pages;
+
+    let final_idx: HashMap<usize, usize> = repaired
+        .iter()
+        .enumerate()
+        .map(|(idx, &page)| (page, idx))
+        .collect();
+
+    // Moving pages in their final order, straight after the page that ends up
+    // before them, means no later move can come between them
+    let mut moved: Vec<usize> = (0..pages.len())
+        .filter(|&idx| !keep[idx])
+        .map(|idx| pages[idx])
+        .collect();
+    moved.sort_by_key(|page| final_idx[page]);
+
+    let moves = moved
+        .into_iter()
+        .map(|page| Move {
+            page,
+            after: final_idx[&page].checked_sub(1).map(|idx| repaired[idx]),
+        })
+        .collect();
+
+    Ok(Repair {
+        pages: repaired,
+        moves,
+    })
+}
+
+/// The largest group of items with no conflicts between them, where conflicts
+/// are transitive. This is the largest antichain of the conflicts, found from
+/// a maximum matching via König's theorem.
+fn largest_conflict_free(n: usize, conflicts: impl Fn(usize, usize) -> bool) -> Vec<bool> {
+    let edges: Vec<Vec<usize>> = (0..n)
+        .map(|i| (0..n).filter(|&j| conflicts(i, j)).collect())
+        .collect();
+
+    // matched[j] is the left item matched with right item j
+    let mut matched: Vec<Option<usize>> = vec![None; n];
+
+    for i in 0..n {
+        augment(i, &edges, &mut matched, &mut vec![false; n]);
+    }
+
+    let mut matched_left = vec![false; n];
+    for i in matched.iter().flatten() {
+        matched_left[*i] = true;
+    }
+
+    // Follow alternating paths from the unmatched left items
+    let mut left_seen = vec![false; n];
+    let mut right_seen = vec![false; n];
+    let mut stack: Vec<usize> = (0..n).filter(|&i| !matched_left[i]).collect();
+
+    while let Some(i) = stack.pop() {
+        if left_seen[i] {
+            continue;
+        }
+        left_seen[i] = true;
+
+        for &j in edges[i].iter() {
+            if !right_seen[j] {
+                right_seen[j] = true;
+
+                if let Some(next) = matched[j] {
+                    stack.push(next);
+                }
+            }
+        }
+    }
+
+    (0..n).map(|i| left_seen[i] && !right_seen[i]).collect()
+}
+
+/// Try to find an augmenting path from a left item (Kuhn's algorithm).
+fn augment(
+    i: usize,
+    edges: &[Vec<usize>],
+    matched: &mut [Option<usize>],
+    seen: &mut [bool],
+) -> bool {
+    for &j in edges[i].iter() {
+        if seen[j] {
+            continue;
+        }
+        seen[j] = true;
+
+        if matched[j].is_none_or(|other| augment(other, edges, matched, seen)) {
+            matched[j] = Some(i);
+            return true;
+        }
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::example_rules;
+
+    fn apply(pages: &[usize], moves: &[Move]) -> Vec<usize> {
+        let mut pages = pages.to_vec();
+
+        for m in moves {
+            pages.retain(|&page| page != m.page);
+
+            let idx = match m.after {
+                Some(after) => pages.iter().position(|&page| page == after).unwrap() + 1,
+                None => 0,
+            };
+            pages.insert(idx, m.page);
+        }
+
+        pages
+    }
+
+    #[test]
+    fn test_repair_example_updates() {
+        let rules = example_rules();
+
+        let result = repair(&[75, 97, 47, 61, 53], &rules).unwrap();
+        assert_eq!(result.pages, vec![97, 75, 47, 61, 53]);
+        assert_eq!(
+            result.moves,
+            vec![Move {
+                page: 75,
+                after: Some(97)
+            }]
+        );
+
+        let result = repair(&[97, 13, 75, 29, 47], &rules).unwrap();
+        assert_eq!(result.pages, vec![97, 75, 47, 29, 13]);
+        assert_eq!(result.moves.len(), 2);
+        assert_eq!(apply(&[97, 13, 75, 29, 47], &result.moves), result.pages);
+    }
+
+    #[test]
+    fn test_valid_update_needs_no_moves() {
+        let result = repair(&[75, 47, 61, 53, 29], &example_rules()).unwrap();
+
+        assert_eq!(result.pages, vec![75, 47, 61, 53, 29]);
+        assert_eq!(result.moves, vec![]);
+    }
+
+    #[test]
+    fn test_repair_changes_less_than_sorting() {
+        // Only 1|2 matters, so moving 2 to the end is enough, while sorting
+        // puts 1 first
+        let rules = RuleIndex::from_iter([(1, 2)]);
+        let pages = [3, 2, 4, 1, 5];
+
+        let result = repair(&pages, &rules).unwrap();
+        assert_eq!(result.moves.len(), 1);
+        assert!(rules.violations(&result.pages).is_empty());
+        assert_eq!(apply(&pages, &result.moves), result.pages);
+    }
+
+    #[test]
+    fn test_repair_uses_rules_through_other_pages() {
+        // 3 must come before 1 through 2, even without a 3|1 rule
+        let rules = RuleIndex::from_iter([(3, 2), (2, 1)]);
+        let pages = [1, 2, 3];
+
+        let result = repair(&pages, &rules).unwrap();
+        assert_eq!(result.pages, vec![3, 2, 1]);
+        assert_eq!(result.moves.len(), 2);
+        assert_eq!(apply(&pages, &result.moves), result.pages);
+    }
+
+    #[test]
+    fn test_repair_with_cycle() {
+        let rules = RuleIndex::from_iter([(1, 2), (2, 1)]);
+        assert!(repair(&[1, 2], &rules).is_err());
+    }
+}
@@ -0,0 +1,134 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::PageRule;
+
+/// The page ordering rules, indexed by the page that must come first.
+#[derive(Debug, Default)]
+pub struct RuleIndex {
+    after: HashMap<usize, HashSet<usize>>,
+}
+
+/// A rule broken by an update: the page that should come first was found at
+/// a later position than the page that should come after it.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Violation {
+    pub rule: PageRule,
+
+    /// The positions of the rule's first and second pages in the update
+    pub positions: (usize, usize),
+}
+
+impl RuleIndex {
+    /// The pages that must come after a page, if both are in an update.
+    pub fn after(&self, page: usize) -> impl Iterator<Item = usize> + '_ {
+        self.after.get(&page).into_iter().flatten().copied()
+    }
+
+    /// Every rule, ordered by first page then second page.
+    pub fn rules(&self) -> Vec<PageRule> {
+        let mut rules: Vec<PageRule> = self
+            .after
+            .iter()
+            .flat_map(|(&before, pages)| pages.iter().map(move |&after| (before, after)))
+            .collect();
+
+        rules.sort();
+        rules
+    }
+
+    pub fn has_rule(&self, before: usize, after: usize) -> bool {
+        self.after
+            .get(&before)
+            .is_some_and(|pages| pages.contains(&after))
+    }
+
+    /// Find every rule broken by an update's pages, ordered by the position of
+    /// the page that should have come first.
+    pub fn violations(&self, pages: &[usize]) -> Vec<Violation> {
+        let positions: HashMap<usize, usize> = pages
+            .iter()
+            .enumerate()
+            .map(|(idx, &page)| (page, idx))
+            .collect();
+
+        let positions = &positions;
+
+        let mut violations: Vec<Violation> = pages
+            .iter()
+            .enumerate()
+            .flat_map(|(idx, &page)| {
+                self.after(page).filter_map(move |after| {
+                    let after_idx = *positions.get(&after)?;
+
+                    (after_idx < idx).then_some(Violation {
+                        rule: (page, after),
+                        positions: (idx, after_idx),
+                    })
+                })
+            })
+            .collect();
+
+        violations.sort_by_key(|violation| violation.positions);
+        violations
+    }
+}
+
+impl FromIterator<PageRule> for RuleIndex {
+    fn from_iter<I: IntoIterator<Item = PageRule>>(rules: I) -> Self {
+        let mut index = RuleIndex::default();
+
+        for (before, after) in rules {
+            index.after.entry(before).or_default().insert(after);
+        }
+
+        index
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_after() {
+        let index = RuleIndex::from_iter([(1, 2), (1, 3), (2, 3)]);
+
+        let mut after: Vec<usize> = index.after(1).collect();
+        after.sort();
+
+        assert_eq!(after, vec![2, 3]);
+        assert_eq!(index.after(3).count(), 0);
+        assert!(index.has_rule(2, 3));
+        assert_eq!(index.rules(), vec![(1, 2), (1, 3), (2, 3)]);
+        assert!(!index.has_rule(3, 2));
+    }
+
+    #[test]
+    fn test_violations() {
+        let index = RuleIndex::from_iter([(97, 75), (97, 13), (29, 13), (75, 13), (75, 29)]);
+
+        assert_eq!(index.violations(&[97, 75, 29, 13]), vec![]);
+
+        assert_eq!(
+            index.violations(&[75, 97, 13, 29]),
+            vec![
+                Violation {
+                    rule: (97, 75),
+                    positions: (1, 0),
+                },
+                Violation {
+                    rule: (29, 13),
+                    positions: (3, 2),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_violations_ignore_missing_pages() {
+        let index = RuleIndex::from_iter([(1, 2), (3, 1)]);
+
+        assert_eq!(index.violations(&[1, 4]), vec![]);
+        assert_eq!(index.violations(&[]), vec![]);
+    }
+}
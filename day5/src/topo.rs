@@ -0,0 +1,196 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::rules::RuleIndex;
+
+/// Pages that can't be ordered because their rules form a cycle. Each page
+/// must come before the next, and the last page before the first.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Cycle {
+    pub pages: Vec<usize>,
+}
+
+/// The result of sorting an update's pages.
+#[derive(Debug, PartialEq, Eq)]
+pub struct TopoOrder {
+    pub pages: Vec<usize>,
+
+    /// Neighbouring pages with no rule between them, which could be swapped
+    /// without breaking any rule. Empty if the order is the only one possible.
+    pub ambiguous: Vec<(usize, usize)>,
+}
+
+/// Sort pages so every rule between them is followed, using only the rules
+/// where both pages are in the update.
+///
+/// When more than one page could come next, the one that appears earliest in
+/// the original pages is picked, so the order is deterministic and pages the
+/// rules don't care about stay where they were.
+pub fn topo_sort(pages: &[usize], rules: &RuleIndex) -> Result<TopoOrder, Cycle> {
+    let graph = Graph::new(pages, rules);
+
+    let mut in_degree: Vec<usize> = vec![0; pages.len()];
+    for successors in graph.successors.iter() {
+        for &idx in successors {
+            in_degree[idx] += 1;
+        }
+    }
+
+    let mut sorted: Vec<usize> = Vec::with_capacity(pages.len());
+    let mut placed = vec![false; pages.len()];
+
+    while sorted.len() < pages.len() {
+        let next = (0..pages.len()).find(|&idx| !placed[idx] && in_degree[idx] == 0);
+
+        let Some(next) = next else {
+            let remaining: Vec<usize> = (0..pages.len()).filter(|&idx| !placed[idx]).collect();
+
+            return Err(Cycle {
+                pages: graph
+                    .find_cycle(&remaining)
+                    .into_iter()
+                    .map(|idx| pages[idx])
+                    .collect(),
+            });
+        };
+
+        placed[next] = true;
+        sorted.push(next);
+
+        for &idx in graph.successors[next].iter() {
+            in_degree[idx] -= 1;
+        }
+    }
+
+    // The order is the only one possible exactly when every page has a rule
+    // putting it before the page after it.
+    let ambiguous = sorted
+        .windows(2)
+        .map(|pair| (pages[pair[0]], pages[pair[1]]))
+        .filter(|&(before, after)| !rules.has_rule(before, after))
+        .collect();
+
+    Ok(TopoOrder {
+        pages: sorted.into_iter().map(|idx| pages[idx]).collect(),
+        ambiguous,
+    })
+}
+
+/// The rules between an update's pages, by index into the pages.
+struct Graph {
+    successors: Vec<HashSet<usize>>,
+}
+
+impl Graph {
+    fn new(pages: &[usize], rules: &RuleIndex) -> Self {
+        let indexes: HashMap<usize, usize> = pages
+            .iter()
+            .enumerate()
+            .map(|(idx, &page)| (page, idx))
+            .collect();
+
+        let mut successors = vec![HashSet::new(); pages.len()];
+
+        for (idx, &page) in pages.iter().enumerate() {
+            successors[idx].extend(rules.after(page).filter_map(|after| indexes.get(&after)));
+        }
+
+        Graph { successors }
+    }
+
+    /// Find a cycle among pages that couldn't be sorted. Every one of them has
+    /// a predecessor in the group, so walking backwards must loop eventually.
+    fn find_cycle(&self, remaining: &[usize]) -> Vec<usize> {
+        let remaining: HashSet<usize> = remaining.iter().copied().collect();
+
+        let predecessor = |idx: usize| {
+            (0..self.successors.len())
+                .filter(|pred| remaining.contains(pred))
+                .find(|&pred| self.successors[pred].contains(&idx))
+                .expect("Unsorted page has no unsorted predecessor")
+        };
+
+        let start = *remaining.iter().min().expect("No pages in cycle");
+        let mut path = vec![start];
+        let mut seen: HashMap<usize, usize> = HashMap::from([(start, 0)]);
+
+        loop {
+            let pred = predecessor(*path.last().expect("Empty path"));
+
+            if let Some(&pos) = seen.get(&pred) {
+                // The path runs backwards, so reverse it to follow the rules
+                let mut cycle = path.split_off(pos);
+                cycle.reverse();
+                return cycle;
+            }
+
+            seen.insert(pred, path.len());
+            path.push(pred);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::example_rules;
+
+    #[test]
+    fn test_sorts_example_updates() {
+        let order = topo_sort(&[75, 97, 47, 61, 53], &example_rules()).unwrap();
+        assert_eq!(order.pages, vec![97, 75, 47, 61, 53]);
+        assert_eq!(order.ambiguous, vec![]);
+
+        let order = topo_sort(&[61, 13, 29], &example_rules()).unwrap();
+        assert_eq!(order.pages, vec![61, 29, 13]);
+
+        let order = topo_sort(&[97, 13, 75, 29, 47], &example_rules()).unwrap();
+        assert_eq!(order.pages, vec![97, 75, 47, 29, 13]);
+    }
+
+    #[test]
+    fn test_unconstrained_pages_keep_their_order() {
+        // Nothing relates 1 and 2 to the other pages or each other
+        let order = topo_sort(&[2, 53, 1, 47], &example_rules()).unwrap();
+
+        assert_eq!(order.pages, vec![2, 1, 47, 53]);
+        assert_eq!(order.ambiguous, vec![(2, 1), (1, 47)]);
+    }
+
+    #[test]
+    fn test_reports_ambiguous_orderings() {
+        // 1 and 2 must both come before 3, but either could be first
+        let rules = RuleIndex::from_iter([(1, 3), (2, 3)]);
+        let order = topo_sort(&[3, 2, 1], &rules).unwrap();
+
+        assert_eq!(order.pages, vec![2, 1, 3]);
+        assert_eq!(order.ambiguous, vec![(2, 1)]);
+    }
+
+    #[test]
+    fn test_ignores_rules_for_other_pages() {
+        // The cycle goes through 4, which isn't in the update
+        let rules = RuleIndex::from_iter([(1, 2), (2, 4), (4, 1)]);
+        let order = topo_sort(&[2, 1], &rules).unwrap();
+
+        assert_eq!(order.pages, vec![1, 2]);
+    }
+
+    #[test]
+    fn test_reports_cycles() {
+        let rules = RuleIndex::from_iter([(1, 2), (2, 3), (3, 1), (0, 1)]);
+
+        assert_eq!(
+            topo_sort(&[0, 3, 2, 1], &rules),
+            Err(Cycle {
+                pages: vec![1, 2, 3]
+            })
+        );
+
+        // A page ordered after a cycle isn't part of it
+        let rules = RuleIndex::from_iter([(1, 2), (2, 1), (2, 5)]);
+        let cycle = topo_sort(&[5, 1, 2], &rules).unwrap_err();
+
+        assert_eq!(cycle.pages.len(), 2);
+        assert!(!cycle.pages.contains(&5));
+    }
+}
@@ -0,0 +1,511 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    hash::Hash,
+    ops::{Bound, RangeBounds},
+    str::FromStr,
+};
+
+use crate::operator::Operator;
+
+/// How the operators in an equation are evaluated.
+#[derive(Clone, Debug, Default)]
+pub enum Evaluation {
+    /// Strictly left to right, ignoring precedence. These are the rules of
+    /// the puzzle.
+    #[default]
+    LeftToRight,
+
+    /// Operators with a higher precedence are applied first. Operators with
+    /// the same precedence are applied left to right.
+    Precedence(Precedence),
+
+    /// The equation may be parenthesised in any way, so precedence plays no
+    /// part in the result.
+    Parenthesised,
+}
+
+impl Evaluation {
+    /// Determine if any combination of the operators makes the inputs equal
+    /// to the test value.
+    pub fn is_solvable(&self, test_value: usize, inputs: &[usize], operators: &[Operator]) -> bool {
+        // No operator can make a value smaller unless one of its operands is
+        // zero, so without zeros any partial result above the test value is
+        // a dead end.
+        let limit = if inputs.contains(&0) {
+            None
+        } else {
+            Some(test_value)
+        };
+
+        self.output_counts(inputs, operators, limit)
+            .contains_key(&test_value)
+    }
+
+    /// Find every result the inputs can reach within the range, along with
+    /// the number of ways of reaching it. Each way is a different choice of
+    /// operators, or for `Parenthesised`, a different choice of operators
+    /// and parentheses. Counts saturate at `u64::MAX`.
+    ///
+    /// For example, the left to right outputs of `[1, 1, 2]` with `+` and `*`:
+    ///
+    /// 1 + 1 + 2 = 4
+    /// 1 + 1 * 2 = 4
+    /// 1 * 1 + 2 = 3
+    /// 1 * 1 * 2 = 2
+    ///
+    /// Final result:
+    /// {2: 1, 3: 1, 4: 2}
+    pub fn reachable(
+        &self,
+        inputs: &[usize],
+        operators: &[Operator],
+        range: impl RangeBounds<usize>,
+    ) -> BTreeMap<usize, u64> {
+        let limit = match range.end_bound() {
+            _ if inputs.contains(&0) => None,
+            Bound::Included(&end) => Some(end),
+            Bound::Excluded(&end) => Some(end.saturating_sub(1)),
+            Bound::Unbounded => None,
+        };
+
+        self.output_counts(inputs, operators, limit)
+            .into_iter()
+            .filter(|(val, _)| range.contains(val))
+            .collect()
+    }
+
+    /// Count the ways of reaching each output, skipping any partial result
+    /// above the limit.
+    fn output_counts(
+        &self,
+        inputs: &[usize],
+        operators: &[Operator],
+        limit: Option<usize>,
+    ) -> HashMap<usize, u64> {
+        let within_limit = |val: &usize| limit.is_none_or(|limit| *val <= limit);
+
+        match self {
+            Evaluation::LeftToRight => left_to_right_outputs(inputs, operators, within_limit),
+            Evaluation::Precedence(precedence) => {
+                precedence_outputs(inputs, operators, precedence, within_limit)
+            }
+            Evaluation::Parenthesised => parenthesised_outputs(inputs, operators, within_limit),
+        }
+    }
+}
+
+/// The precedence of each operator. Higher levels are applied first.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Precedence {
+    add: u8,
+    multiply: u8,
+    concatenate: u8,
+}
+
+impl Precedence {
+    pub fn level(&self, operator: Operator) -> u8 {
+        match operator {
+            Operator::Add => self.add,
+            Operator::Multiply => self.multiply,
+            Operator::Concatenate => self.concatenate,
+        }
+    }
+
+    /// Change the precedence of a single operator.
+    pub fn with(mut self, operator: Operator, level: u8) -> Self {
+        match operator {
+            Operator::Add => self.add = level,
+            Operator::Multiply => self.multiply = level,
+            Operator::Concatenate => self.concatenate = level,
+        }
+
+        self
+    }
+}
+
+impl Default for Precedence {
+    /// `*` before `+`, as usual. Concatenation binds tightest, since joining
+    /// digits produces a single number.
+    fn default() -> Self {
+        Precedence {
+            add: 0,
+            multiply: 1,
+            concatenate: 2,
+        }
+    }
+}
+
+impl FromStr for Precedence {
+    type Err = String;
+
+    /// Parse precedence levels from lowest to highest, separated by `<`.
+    /// Operators sharing a level are separated by `,`. For example, `+,||<*`
+    /// applies `*` first, then `+` and `||` left to right. Operators that are
+    /// not listed keep their default level.
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        spec.split('<').enumerate().try_fold(
+            Precedence::default(),
+            |precedence, (level, operators)| {
+                operators
+                    .split(',')
+                    .try_fold(precedence, |precedence, operator| {
+                        Ok(precedence.with(operator.trim().parse()?, level as u8))
+                    })
+            },
+        )
+    }
+}
+
+/// Find all possible equation output. For example:
+///
+/// input:
+/// [2, 4, 6]
+///
+/// outputs (in order, ignore order of operation):
+/// 2 + 4 + 6 = 12
+/// 2 + 4 * 6 = 36
+/// 2 * 4 + 6 = 14
+/// 2 * 4 * 6 = 48
+///
+/// Final result:
+/// vec![12, 36, 14, 48]
+///
+/// Results that overflow a `usize` are dropped, since they can never match a
+/// test value. Each output is counted once per way of reaching it.
+fn left_to_right_outputs(
+    inputs: &[usize],
+    operators: &[Operator],
+    within_limit: impl Fn(&usize) -> bool,
+) -> HashMap<usize, u64> {
+    let Some((&first, rest)) = inputs.split_first() else {
+        return HashMap::new();
+    };
+
+    // Seed with the first value rather than 0, otherwise `0 * first` would
+    // act as a way of dropping the first value from the equation.
+    rest.iter()
+        .fold(HashMap::from([(first, 1)]), |outputs, &val| {
+            let mut new_outputs = HashMap::new();
+            for (&acc_val, &count) in outputs.iter() {
+                operators
+                    .iter()
+                    .filter_map(|op| op.apply(acc_val, val))
+                    .filter(&within_limit)
+                    .for_each(|output| add_count(&mut new_outputs, output, count));
+            }
+            new_outputs
+        })
+        .into_iter()
+        .filter(|(output, _)| within_limit(output))
+        .collect()
+}
+
+/// A partially evaluated equation, in the style of the shunting-yard
+/// algorithm. `pending` holds the left hand sides still waiting for a
+/// higher precedence operation to finish, with strictly increasing
+/// precedence from bottom to top.
+///
+/// For `2 + 3 * 4` with the default precedence, after reading `4` this is:
+/// pending: [(2, +), (3, *)]
+/// value: 4
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct PartialEquation {
+    pending: Vec<(usize, Operator)>,
+    value: usize,
+}
+
+impl PartialEquation {
+    /// Apply every pending operation with at least the given precedence level.
+    fn reduce(&mut self, precedence: &Precedence, min_level: u8) -> Option<()> {
+        while let Some(&(lhs, op)) = self.pending.last() {
+            if precedence.level(op) < min_level {
+                break;
+            }
+
+            self.value = op.apply(lhs, self.value)?;
+            self.pending.pop();
+        }
+
+        Some(())
+    }
+
+    fn push(&self, precedence: &Precedence, op: Operator, val: usize) -> Option<Self> {
+        let mut next = self.clone();
+        next.reduce(precedence, precedence.level(op))?;
+        next.pending.push((next.value, op));
+        next.value = val;
+
+        Some(next)
+    }
+
+    fn finish(mut self, precedence: &Precedence) -> Option<usize> {
+        self.reduce(precedence, 0)?;
+        Some(self.value)
+    }
+
+    fn values(&self) -> impl Iterator<Item = &usize> {
+        self.pending
+            .iter()
+            .map(|(lhs, _)| lhs)
+            .chain(std::iter::once(&self.value))
+    }
+}
+
+/// Find all possible outputs when operators are applied according to their
+/// precedence. Rather than building every equation, only the distinct partial
+/// equations are kept after each input, so equations that share a partial
+/// state are evaluated once.
+fn precedence_outputs(
+    inputs: &[usize],
+    operators: &[Operator],
+    precedence: &Precedence,
+    within_limit: impl Fn(&usize) -> bool,
+) -> HashMap<usize, u64> {
+    let Some((&first, rest)) = inputs.split_first() else {
+        return HashMap::new();
+    };
+
+    let start = PartialEquation {
+        pending: vec![],
+        value: first,
+    };
+
+    let states = rest
+        .iter()
+        .fold(HashMap::from([(start, 1)]), |states, &val| {
+            let mut new_states = HashMap::new();
+            for (state, &count) in states.iter() {
+                operators
+                    .iter()
+                    .filter_map(|&op| state.push(precedence, op, val))
+                    .filter(|state| state.values().all(&within_limit))
+                    .for_each(|state| add_count(&mut new_states, state, count));
+            }
+            new_states
+        });
+
+    let mut outputs = HashMap::new();
+    for (state, count) in states {
+        if let Some(output) = state.finish(precedence).filter(&within_limit) {
+            add_count(&mut outputs, output, count);
+        }
+    }
+
+    outputs
+}
+
+/// Find all possible outputs for any parenthesisation of the equation. The
+/// outputs of every contiguous run of inputs are built from the outputs of
+/// the shorter runs either side of each possible final operator.
+fn parenthesised_outputs(
+    inputs: &[usize],
+    operators: &[Operator],
+    within_limit: impl Fn(&usize) -> bool,
+) -> HashMap<usize, u64> {
+    let len = inputs.len();
+    if len == 0 {
+        return HashMap::new();
+    }
+
+    // outputs[start][end] holds the outputs of inputs[start..=end]
+    let mut outputs: Vec<Vec<HashMap<usize, u64>>> = vec![vec![HashMap::new(); len]; len];
+
+    for (i, &val) in inputs.iter().enumerate() {
+        if within_limit(&val) {
+            outputs[i][i].insert(val, 1);
+        }
+    }
+
+    for run in 2..=len {
+        for start in 0..=(len - run) {
+            let end = start + run - 1;
+            let mut run_outputs = HashMap::new();
+
+            for split in start..end {
+                for (&lhs, &lhs_count) in outputs[start][split].iter() {
+                    for (&rhs, &rhs_count) in outputs[split + 1][end].iter() {
+                        let count = lhs_count.saturating_mul(rhs_count);
+
+                        operators
+                            .iter()
+                            .filter_map(|op| op.apply(lhs, rhs))
+                            .filter(&within_limit)
+                            .for_each(|output| add_count(&mut run_outputs, output, count));
+                    }
+                }
+            }
+
+            outputs[start][end] = run_outputs;
+        }
+    }
+
+    std::mem::take(&mut outputs[0][len - 1])
+}
+
+fn add_count<K: Eq + Hash>(counts: &mut HashMap<K, u64>, key: K, count: u64) {
+    let total = counts.entry(key).or_insert(0);
+    *total = total.saturating_add(count);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    const ADD_MUL: [Operator; 2] = [Operator::Add, Operator::Multiply];
+
+    impl Evaluation {
+        fn outputs(&self, inputs: &[usize], operators: &[Operator]) -> HashSet<usize> {
+            self.reachable(inputs, operators, ..).into_keys().collect()
+        }
+    }
+
+    #[test]
+    fn test_left_to_right_outputs() {
+        let outputs = Evaluation::LeftToRight.outputs(&[2, 4, 6], &ADD_MUL);
+
+        assert_eq!(outputs, HashSet::from([12, 36, 14, 48]));
+    }
+
+    #[test]
+    fn test_precedence_outputs() {
+        let evaluation = Evaluation::Precedence(Precedence::default());
+        let outputs = evaluation.outputs(&[2, 4, 6], &ADD_MUL);
+
+        // 2 + 4 * 6 = 26 and 2 * 4 + 6 = 14
+        assert_eq!(outputs, HashSet::from([12, 26, 14, 48]));
+    }
+
+    #[test]
+    fn test_custom_precedence() {
+        // `+` before `*`
+        let precedence = Precedence::default().with(Operator::Add, 2);
+        let evaluation = Evaluation::Precedence(precedence);
+        let outputs = evaluation.outputs(&[2, 4, 6], &ADD_MUL);
+
+        // 2 + 4 * 6 = 36 and 2 * 4 + 6 = 20
+        assert_eq!(outputs, HashSet::from([12, 36, 20, 48]));
+    }
+
+    #[test]
+    fn test_precedence_with_concatenation() {
+        let operators = [Operator::Add, Operator::Concatenate];
+
+        let tight = Evaluation::Precedence(Precedence::default());
+        // 1 + 2 || 3 = 1 + 23
+        assert!(tight.outputs(&[1, 2, 3], &operators).contains(&24));
+
+        let loose = Evaluation::Precedence(Precedence::default().with(Operator::Concatenate, 0));
+        // 1 + 2 || 3 = 3 || 3
+        assert!(loose.outputs(&[1, 2, 3], &operators).contains(&33));
+    }
+
+    #[test]
+    fn test_parse_precedence() {
+        let precedence: Precedence = "+,||<*".parse().unwrap();
+
+        assert_eq!(precedence.level(Operator::Add), 0);
+        assert_eq!(precedence.level(Operator::Concatenate), 0);
+        assert_eq!(precedence.level(Operator::Multiply), 1);
+
+        assert!("+<-".parse::<Precedence>().is_err());
+    }
+
+    #[test]
+    fn test_precedence_matches_brute_force() {
+        let inputs = [3, 1, 4, 1, 5, 9, 2];
+        let evaluation = Evaluation::Precedence(Precedence::default());
+        let outputs = evaluation.outputs(&inputs, &ADD_MUL);
+
+        // Every `+` splits the equation into products which are then summed
+        let gaps = inputs.len() - 1;
+        let expected: HashSet<usize> = (0..1usize << gaps)
+            .map(|adds| {
+                let mut sum = 0;
+                let mut product = inputs[0];
+                for (gap, &val) in inputs[1..].iter().enumerate() {
+                    if adds & (1 << gap) != 0 {
+                        sum += product;
+                        product = val;
+                    } else {
+                        product *= val;
+                    }
+                }
+                sum + product
+            })
+            .collect();
+
+        assert_eq!(outputs, expected);
+    }
+
+    #[test]
+    fn test_parenthesised_outputs() {
+        let outputs = Evaluation::Parenthesised.outputs(&[2, 4, 6], &ADD_MUL);
+
+        // Adds 2 * (4 + 6) = 20 and 2 + (4 * 6) = 26 to the left to right outputs
+        assert_eq!(outputs, HashSet::from([12, 36, 14, 48, 20, 26]));
+    }
+
+    #[test]
+    fn test_is_solvable_with_zero_inputs() {
+        // Partial results above the test value must not be pruned when a
+        // later zero can bring them back down.
+        let evaluation = Evaluation::Precedence(Precedence::default());
+
+        assert!(evaluation.is_solvable(1, &[9, 9, 0, 1], &ADD_MUL));
+        assert!(Evaluation::Parenthesised.is_solvable(1, &[9, 9, 0, 1], &ADD_MUL));
+    }
+
+    #[test]
+    fn test_reachable_counts() {
+        let reachable = Evaluation::LeftToRight.reachable(&[1, 1, 2], &ADD_MUL, ..);
+
+        assert_eq!(reachable, BTreeMap::from([(2, 1), (3, 1), (4, 2)]));
+    }
+
+    #[test]
+    fn test_reachable_counts_every_operator_sequence() {
+        let operators = [Operator::Add, Operator::Multiply, Operator::Concatenate];
+        let inputs = [2, 1, 3, 1, 2];
+        let sequences = 3u64.pow(inputs.len() as u32 - 1);
+
+        let evaluation = Evaluation::Precedence(Precedence::default());
+        let reachable = evaluation.reachable(&inputs, &operators, ..);
+        assert_eq!(reachable.values().sum::<u64>(), sequences);
+
+        let reachable = Evaluation::LeftToRight.reachable(&inputs, &operators, ..);
+        assert_eq!(reachable.values().sum::<u64>(), sequences);
+    }
+
+    #[test]
+    fn test_reachable_parenthesised_counts() {
+        // 1 + (1 + 1) and (1 + 1) + 1 are counted separately
+        let reachable = Evaluation::Parenthesised.reachable(&[1, 1, 1], &[Operator::Add], ..);
+
+        assert_eq!(reachable, BTreeMap::from([(3, 2)]));
+    }
+
+    #[test]
+    fn test_reachable_within_range() {
+        let reachable = Evaluation::LeftToRight.reachable(&[2, 4, 6], &ADD_MUL, 13..40);
+        assert_eq!(reachable, BTreeMap::from([(14, 1), (36, 1)]));
+
+        let reachable = Evaluation::LeftToRight.reachable(&[2, 4, 6], &ADD_MUL, ..=14);
+        assert_eq!(reachable, BTreeMap::from([(12, 1), (14, 1)]));
+    }
+
+    #[test]
+    fn test_reachable_within_range_with_zero_inputs() {
+        // 9 * 9 goes out of range before the zero brings it back down
+        let reachable = Evaluation::LeftToRight.reachable(&[9, 9, 0], &ADD_MUL, ..10);
+
+        assert_eq!(reachable, BTreeMap::from([(0, 2)]));
+    }
+
+    #[test]
+    fn test_empty_inputs() {
+        assert!(Evaluation::LeftToRight.outputs(&[], &ADD_MUL).is_empty());
+        assert!(Evaluation::Parenthesised.outputs(&[], &ADD_MUL).is_empty());
+    }
+}
@@ -1,69 +1,107 @@
+mod evaluation;
+mod operator;
+mod stream;
+
+use std::{fs::File, io::BufReader, thread};
+
+use evaluation::{Evaluation, Precedence};
+use operator::Operator;
+use stream::Verdict;
+
 fn main() {
+    // The puzzle evaluates strictly left to right, but the other modes are
+    // useful for exploring variants of it.
+    let evaluation = if shared::has_flag("--parenthesised") {
+        Evaluation::Parenthesised
+    } else if let Some(spec) = shared::flag_value("--precedence") {
+        Evaluation::Precedence(spec.parse().expect("Invalid precedence"))
+    } else if shared::has_flag("--precedence") {
+        Evaluation::Precedence(Precedence::default())
+    } else {
+        Evaluation::LeftToRight
+    };
+
+    if shared::has_flag("--stream") {
+        stream_puzzle(&evaluation);
+        return;
+    }
+
     let input = shared::read_file_from_args();
     let puzzle = Puzzle::from(input.as_str());
 
-    let part_one = part_one(&puzzle);
+    let part_one = part_one(&puzzle, &evaluation);
     println!("part one: {}", part_one);
 
-    let part_two = part_two(&puzzle);
+    let part_two = part_two(&puzzle, &evaluation);
     println!("part one: {}", part_two);
+
+    if shared::has_flag("--reachable") {
+        print_reachable(&puzzle, &evaluation);
+    }
 }
 
-fn part_one(puzzle: &Puzzle) -> usize {
-    solve_puzzle(puzzle, false)
+const PART_ONE_OPERATORS: [Operator; 2] = [Operator::Add, Operator::Multiply];
+
+const PART_TWO_OPERATORS: [Operator; 3] =
+    [Operator::Add, Operator::Multiply, Operator::Concatenate];
+
+fn part_one(puzzle: &Puzzle, evaluation: &Evaluation) -> usize {
+    solve_puzzle(puzzle, &PART_ONE_OPERATORS, evaluation)
 }
 
-fn part_two(puzzle: &Puzzle) -> usize {
-    solve_puzzle(puzzle, true)
+fn part_two(puzzle: &Puzzle, evaluation: &Evaluation) -> usize {
+    solve_puzzle(puzzle, &PART_TWO_OPERATORS, evaluation)
 }
 
-fn solve_puzzle(puzzle: &Puzzle, allow_concat: bool) -> usize {
+fn solve_puzzle(puzzle: &Puzzle, operators: &[Operator], evaluation: &Evaluation) -> usize {
     puzzle
         .data
         .iter()
-        .filter(|(test_value, inputs)| has_valid_operator_combo(*test_value, inputs, allow_concat))
+        .filter(|(test_value, inputs)| evaluation.is_solvable(*test_value, inputs, operators))
         .fold(0, |acc, (test_value, _)| acc + test_value)
 }
 
-fn has_valid_operator_combo(test_value: usize, inputs: &[usize], allow_concat: bool) -> bool {
-    let output = all_possible_outputs(inputs, allow_concat);
-    output.iter().any(|&val| val == test_value)
+/// Solve the input file line by line without reading it all into memory,
+/// printing a verdict for each line as it is solved.
+fn stream_puzzle(evaluation: &Evaluation) {
+    let file = File::open(shared::filename_from_args()).expect("Failed to open file");
+    let workers = thread::available_parallelism().map_or(1, usize::from);
+
+    let totals = stream::solve_stream(
+        BufReader::new(file),
+        &[&PART_ONE_OPERATORS, &PART_TWO_OPERATORS],
+        evaluation,
+        workers,
+        |line_number, verdict, totals| match verdict {
+            Verdict::Solved {
+                test_value,
+                solvable,
+            } => println!(
+                "line {}: {} part one: {}, part two: {} (totals: {}, {})",
+                line_number, test_value, solvable[0], solvable[1], totals[0], totals[1]
+            ),
+            Verdict::Invalid(err) => println!("line {}: invalid: {}", line_number, err),
+        },
+    );
+
+    println!("part one: {}", totals[0]);
+    println!("part two: {}", totals[1]);
 }
 
-/// Find all possible equation output. For example:
-///
-/// input:
-/// [2, 4, 6]
-///
-/// outputs (in order, ignore order of operation):
-/// 2 + 4 + 6 = 12
-/// 2 + 4 * 6 = 36
-/// 2 * 4 + 6 = 14
-/// 2 * 4 * 6 = 48
-///
-/// Final result:
-/// vec![6, 9, 5, 6]
-fn all_possible_outputs(inputs: &[usize], allow_concat: bool) -> Vec<usize> {
-    inputs.iter().fold(vec![0], |outputs, &val| {
-        let mut new_outputs = vec![];
-        for &acc_val in outputs.iter() {
-            new_outputs.push(acc_val + val);
-            new_outputs.push(acc_val * val);
-
-            if allow_concat {
-                new_outputs.push(concat_number_strings(acc_val, val));
-            }
-        }
-        new_outputs
-    })
-}
-
-/// Concatenate two numbers together. For example:
-/// 2, 4 -> 24
-/// 10, 5 -> 105
-fn concat_number_strings(a: usize, b: usize) -> usize {
-    // Multiply a by 10^b's digit
-    a * 10usize.pow(b.ilog10() + 1) + b
+/// Print how many distinct results each equation can reach with the part two
+/// operators, and how many ways there are of reaching its test value.
+fn print_reachable(puzzle: &Puzzle, evaluation: &Evaluation) {
+    for (test_value, inputs) in puzzle.data.iter() {
+        let reachable = evaluation.reachable(inputs, &PART_TWO_OPERATORS, ..);
+        let ways = reachable.get(test_value).copied().unwrap_or(0);
+
+        println!(
+            "{}: {} distinct results, {} ways to reach the test value",
+            test_value,
+            reachable.len(),
+            ways
+        );
+    }
 }
 
 #[derive(Debug)]
@@ -76,30 +114,48 @@ impl From<&str> for Puzzle {
         let data = input
             .trim()
             .lines()
-            .map(|line| {
-                let line = line.trim();
-                let (test_value, inputs) = line.split_once(": ").expect("Invalid puzzle line");
-
-                let test_value = test_value.parse().expect("Unable to parse test value");
-                let inputs = inputs
-                    .split_whitespace()
-                    .map(|val| val.parse().expect("Unable to parse input value"))
-                    .collect();
-
-                (test_value, inputs)
-            })
+            .map(|line| parse_equation(line).unwrap_or_else(|err| panic!("{}", err)))
             .collect();
 
         Puzzle { data }
     }
 }
 
-#[cfg(test)]
+/// Parse a single puzzle line, e.g. `190: 10 19`.
+fn parse_equation(line: &str) -> Result<(usize, Vec<usize>), String> {
+    let line = line.trim();
+    let (test_value, inputs) = line
+        .split_once(": ")
+        .ok_or_else(|| format!("Invalid puzzle line: {}", line))?;
+
+    let test_value = test_value
+        .parse()
+        .map_err(|_| format!("Unable to parse test value: {}", test_value))?;
+    let inputs = inputs
+        .split_whitespace()
+        .map(parse_input_value)
+        .collect::<Result<_, _>>()?;
+
+    Ok((test_value, inputs))
+}
+
+/// Parse a single input value. `0` is a valid input, but values with leading
+/// zeros (e.g. `05`) are rejected: concatenating `1` and `05` would be `105`
+/// as text but `15` as a number, so the equation has no single meaning.
+fn parse_input_value(val: &str) -> Result<usize, String> {
+    if val.len() > 1 && val.starts_with('0') {
+        return Err(format!("Input value has leading zeros: {}", val));
+    }
+
+    val.parse()
+        .map_err(|_| format!("Unable to parse input value: {}", val))
+}
 
+#[cfg(test)]
 mod tests {
     use super::*;
 
-    const INPUT: &str = r#"
+    pub(crate) const INPUT: &str = r#"
         190: 10 19
         3267: 81 40 27
         83: 17 5
@@ -114,7 +170,7 @@ mod tests {
     #[test]
     fn test_part_one() {
         let puzzle = Puzzle::from(INPUT);
-        let result = part_one(&puzzle);
+        let result = part_one(&puzzle, &Evaluation::LeftToRight);
 
         assert_eq!(result, 3749);
     }
@@ -122,8 +178,68 @@ mod tests {
     #[test]
     fn test_part_two() {
         let puzzle = Puzzle::from(INPUT);
-        let result = part_two(&puzzle);
+        let result = part_two(&puzzle, &Evaluation::LeftToRight);
 
         assert_eq!(result, 11387);
     }
+
+    #[test]
+    fn test_zero_input_values() {
+        let puzzle = Puzzle::from(
+            r#"
+            70: 7 0
+            0: 0 0
+            5: 0 5
+            105: 10 5 0
+            "#,
+        );
+
+        assert_eq!(part_one(&puzzle, &Evaluation::LeftToRight), 5);
+        assert_eq!(part_two(&puzzle, &Evaluation::LeftToRight), 180);
+    }
+
+    #[test]
+    fn test_first_value_cannot_be_dropped() {
+        // `0 * 3 + 5` used to be considered a valid equation.
+        let puzzle = Puzzle::from("5: 3 5");
+
+        assert_eq!(part_one(&puzzle, &Evaluation::LeftToRight), 0);
+    }
+
+    #[test]
+    fn test_overflowing_outputs_are_ignored() {
+        let puzzle = Puzzle::from(format!("{}: {} 9 9", usize::MAX, usize::MAX).as_str());
+
+        assert_eq!(part_two(&puzzle, &Evaluation::LeftToRight), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "leading zeros")]
+    fn test_leading_zero_input_value() {
+        let _ = Puzzle::from("105: 1 05");
+    }
+
+    #[test]
+    fn test_part_one_with_precedence() {
+        let puzzle = Puzzle::from(INPUT);
+        let evaluation = Evaluation::Precedence(Precedence::default());
+
+        // 292: 11 + 6 * 16 + 20 is now 127, so only the equations that
+        // don't rely on adding before multiplying remain.
+        assert_eq!(part_one(&puzzle, &evaluation), 190 + 3267);
+    }
+
+    #[test]
+    fn test_part_one_parenthesised() {
+        let puzzle = Puzzle::from(INPUT);
+        let result = part_one(&puzzle, &Evaluation::Parenthesised);
+
+        // No example equation needs anything but left to right
+        assert_eq!(result, 3749);
+
+        // 2 * (3 + 4) is only possible with parentheses
+        let puzzle = Puzzle::from("14: 2 3 4");
+        assert_eq!(part_one(&puzzle, &Evaluation::LeftToRight), 0);
+        assert_eq!(part_one(&puzzle, &Evaluation::Parenthesised), 14);
+    }
 }
@@ -0,0 +1,102 @@
+use std::str::FromStr;
+
+/// The operators that can be placed between the values of an equation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Operator {
+    Add,
+    Multiply,
+    Concatenate,
+}
+
+impl Operator {
+    /// Apply the operator to two values. Returns `None` if the result does
+    /// not fit in a `usize`.
+    pub fn apply(self, a: usize, b: usize) -> Option<usize> {
+        match self {
+            Operator::Add => a.checked_add(b),
+            Operator::Multiply => a.checked_mul(b),
+            Operator::Concatenate => concatenate(a, b),
+        }
+    }
+}
+
+impl FromStr for Operator {
+    type Err = String;
+
+    fn from_str(symbol: &str) -> Result<Self, Self::Err> {
+        match symbol {
+            "+" => Ok(Operator::Add),
+            "*" => Ok(Operator::Multiply),
+            "||" => Ok(Operator::Concatenate),
+            _ => Err(format!("Unknown operator: {}", symbol)),
+        }
+    }
+}
+
+/// Count the decimal digits in a number. Zero is written as a single digit.
+pub fn digit_count(n: usize) -> u32 {
+    match n.checked_ilog10() {
+        Some(log) => log + 1,
+        None => 1,
+    }
+}
+
+/// Concatenate the decimal digits of two numbers. For example:
+/// 2, 4 -> 24
+/// 10, 5 -> 105
+/// 7, 0 -> 70
+///
+/// Returns `None` if the result does not fit in a `usize`.
+pub fn concatenate(a: usize, b: usize) -> Option<usize> {
+    10usize
+        .checked_pow(digit_count(b))
+        .and_then(|shift| a.checked_mul(shift))
+        .and_then(|shifted| shifted.checked_add(b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_digit_count() {
+        assert_eq!(digit_count(0), 1);
+        assert_eq!(digit_count(9), 1);
+        assert_eq!(digit_count(10), 2);
+        assert_eq!(digit_count(usize::MAX), usize::MAX.to_string().len() as u32);
+    }
+
+    #[test]
+    fn test_concatenate_with_zero() {
+        assert_eq!(concatenate(0, 0), Some(0));
+        assert_eq!(concatenate(0, 5), Some(5));
+        assert_eq!(concatenate(7, 0), Some(70));
+        assert_eq!(concatenate(10, 0), Some(100));
+    }
+
+    #[test]
+    fn test_concatenate_overflow() {
+        assert_eq!(concatenate(usize::MAX, 1), None);
+        assert_eq!(concatenate(1, usize::MAX), None);
+    }
+
+    proptest! {
+        #[test]
+        fn digit_count_matches_string_length(n: usize) {
+            prop_assert_eq!(digit_count(n) as usize, n.to_string().len());
+        }
+
+        #[test]
+        fn concatenate_matches_string_concatenation(a: usize, b: usize) {
+            let expected = format!("{}{}", a, b).parse::<usize>().ok();
+            prop_assert_eq!(concatenate(a, b), expected);
+        }
+
+        #[test]
+        fn concatenate_matches_string_concatenation_for_small_values(a in 0..100_000usize, b in 0..100_000usize) {
+            let expected = format!("{}{}", a, b).parse::<usize>().ok();
+            prop_assert_eq!(concatenate(a, b), expected);
+        }
+    }
+}
@@ -0,0 +1,303 @@
+use std::{
+    collections::BTreeMap,
+    io::BufRead,
+    sync::{mpsc, Mutex},
+    thread,
+};
+
+use crate::{evaluation::Evaluation, operator::Operator, parse_equation};
+
+/// The outcome of solving a single line of the input.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Verdict {
+    /// Whether the equation can be solved with each of the operator sets.
+    Solved {
+        test_value: usize,
+        solvable: Vec<bool>,
+    },
+
+    /// The line could not be read or parsed.
+    Invalid(String),
+}
+
+/// A line waiting to be solved: its position in the queue, its line number
+/// in the input, and its contents.
+type Job = (usize, usize, Result<String, String>);
+
+/// Solve equations as they are read, one line at a time, on a pool of worker
+/// threads. Blank lines are skipped.
+///
+/// `on_verdict` is called in input order with the line number, the verdict
+/// and the running total for each operator set. At most four lines per
+/// worker are held in memory at once, however long the input is.
+///
+/// Returns the final total for each operator set. The totals are `u128`, as
+/// adding up millions of `usize` test values could overflow a `usize`.
+pub fn solve_stream(
+    reader: impl BufRead + Send,
+    operator_sets: &[&[Operator]],
+    evaluation: &Evaluation,
+    workers: usize,
+    mut on_verdict: impl FnMut(usize, &Verdict, &[u128]),
+) -> Vec<u128> {
+    let workers = workers.max(1);
+    let capacity = workers * 4;
+
+    let (job_tx, job_rx) = mpsc::sync_channel::<Job>(capacity);
+    let job_rx = Mutex::new(job_rx);
+    let (verdict_tx, verdict_rx) = mpsc::channel();
+
+    // The reader takes a credit for each line it queues, and gets it back
+    // once that line's verdict has been emitted. This bounds the number of
+    // lines in flight even when one slow line holds up the emitting of
+    // later verdicts.
+    let (credit_tx, credit_rx) = mpsc::sync_channel(capacity);
+    for _ in 0..capacity {
+        credit_tx.send(()).expect("Credit channel closed");
+    }
+
+    let mut totals: Vec<u128> = vec![0; operator_sets.len()];
+
+    thread::scope(|scope| {
+        scope.spawn(move || {
+            let mut lines = reader
+                .lines()
+                .enumerate()
+                .filter(|(_, line)| line.as_ref().map_or(true, |line| !line.trim().is_empty()))
+                .enumerate();
+
+            // Take the credit before reading the line, so no more than
+            // `capacity` lines have been read but not emitted
+            while credit_rx.recv().is_ok() {
+                let Some((seq, (idx, line))) = lines.next() else {
+                    break;
+                };
+                let is_err = line.is_err();
+
+                let job = (seq, idx + 1, line.map_err(|err| err.to_string()));
+                if job_tx.send(job).is_err() || is_err {
+                    break;
+                }
+            }
+        });
+
+        for _ in 0..workers {
+            let job_rx = &job_rx;
+            let verdict_tx = verdict_tx.clone();
+
+            scope.spawn(move || loop {
+                let job = job_rx.lock().expect("Job queue poisoned").recv();
+                let Ok((seq, line_number, line)) = job else {
+                    break;
+                };
+
+                let verdict = match line {
+                    Ok(line) => solve_line(&line, operator_sets, evaluation),
+                    Err(err) => Verdict::Invalid(err),
+                };
+
+                if verdict_tx.send((seq, line_number, verdict)).is_err() {
+                    break;
+                }
+            });
+        }
+
+        drop(verdict_tx);
+
+        // Owned here so that if `on_verdict` panics, the reader stops waiting
+        // for credits and the threads can finish
+        let credit_tx = credit_tx;
+
+        // Verdicts arrive in whatever order the workers finish, so hold on
+        // to them until all the earlier lines have been emitted.
+        let mut waiting = BTreeMap::new();
+        let mut next_seq = 0;
+
+        for (seq, line_number, verdict) in verdict_rx {
+            waiting.insert(seq, (line_number, verdict));
+
+            while let Some((line_number, verdict)) = waiting.remove(&next_seq) {
+                if let Verdict::Solved {
+                    test_value,
+                    solvable,
+                } = &verdict
+                {
+                    for (total, _) in totals.iter_mut().zip(solvable).filter(|(_, &ok)| ok) {
+                        *total += *test_value as u128;
+                    }
+                }
+
+                on_verdict(line_number, &verdict, &totals);
+                next_seq += 1;
+
+                // The reader may already have finished
+                let _ = credit_tx.send(());
+            }
+        }
+    });
+
+    totals
+}
+
+fn solve_line(line: &str, operator_sets: &[&[Operator]], evaluation: &Evaluation) -> Verdict {
+    match parse_equation(line) {
+        Ok((test_value, inputs)) => Verdict::Solved {
+            test_value,
+            solvable: operator_sets
+                .iter()
+                .map(|operators| evaluation.is_solvable(test_value, &inputs, operators))
+                .collect(),
+        },
+        Err(err) => Verdict::Invalid(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{self, Read},
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    use super::*;
+    use crate::{tests::INPUT, PART_ONE_OPERATORS, PART_TWO_OPERATORS};
+
+    const OPERATOR_SETS: [&[Operator]; 2] = [&PART_ONE_OPERATORS, &PART_TWO_OPERATORS];
+
+    #[test]
+    fn test_stream_totals() {
+        for workers in [1, 2, 8] {
+            let totals = solve_stream(
+                INPUT.as_bytes(),
+                &OPERATOR_SETS,
+                &Evaluation::LeftToRight,
+                workers,
+                |_, _, _| {},
+            );
+
+            assert_eq!(totals, vec![3749, 11387]);
+        }
+    }
+
+    #[test]
+    fn test_stream_verdicts_are_in_order() {
+        let mut verdicts = vec![];
+
+        solve_stream(
+            INPUT.as_bytes(),
+            &OPERATOR_SETS,
+            &Evaluation::LeftToRight,
+            4,
+            |line_number, verdict, totals| {
+                verdicts.push((line_number, totals.to_vec()));
+
+                if line_number == 5 {
+                    assert_eq!(
+                        *verdict,
+                        Verdict::Solved {
+                            test_value: 156,
+                            solvable: vec![false, true],
+                        }
+                    );
+                }
+            },
+        );
+
+        let line_numbers: Vec<usize> = verdicts.iter().map(|(line, _)| *line).collect();
+        assert_eq!(line_numbers, (2..=10).collect::<Vec<_>>());
+
+        assert_eq!(verdicts[0].1, vec![190, 190]);
+        assert_eq!(verdicts[3].1, vec![3457, 3613]);
+    }
+
+    #[test]
+    fn test_stream_skips_blank_lines_and_reports_invalid_lines() {
+        let input = "\n190: 10 19\n\n  \nnot an equation\n105: 1 05\n";
+        let mut verdicts = vec![];
+
+        let totals = solve_stream(
+            input.as_bytes(),
+            &OPERATOR_SETS,
+            &Evaluation::LeftToRight,
+            2,
+            |line_number, verdict, _| {
+                verdicts.push((line_number, matches!(verdict, Verdict::Invalid(_))));
+            },
+        );
+
+        assert_eq!(verdicts, vec![(2, false), (5, true), (6, true)]);
+        assert_eq!(totals, vec![190, 190]);
+    }
+
+    /// Counts the lines taken from the input, as they are consumed.
+    struct CountingReader<'a> {
+        data: &'a [u8],
+        lines_read: &'a AtomicUsize,
+    }
+
+    impl Read for CountingReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let read = self.fill_buf()?.read(buf)?;
+            self.consume(read);
+            Ok(read)
+        }
+    }
+
+    impl BufRead for CountingReader<'_> {
+        fn fill_buf(&mut self) -> io::Result<&[u8]> {
+            Ok(self.data)
+        }
+
+        fn consume(&mut self, amt: usize) {
+            let newlines = self.data[..amt].iter().filter(|&&b| b == b'\n').count();
+            self.lines_read.fetch_add(newlines, Ordering::SeqCst);
+            self.data = &self.data[amt..];
+        }
+    }
+
+    #[test]
+    fn test_stream_holds_a_bounded_number_of_lines() {
+        // Far more lines than can be in flight at once
+        let input = "3: 1 2\n".repeat(10_000);
+        let lines_read = AtomicUsize::new(0);
+        let reader = CountingReader {
+            data: input.as_bytes(),
+            lines_read: &lines_read,
+        };
+
+        let workers = 3;
+        let mut emitted = 0;
+
+        let totals = solve_stream(
+            reader,
+            &OPERATOR_SETS,
+            &Evaluation::LeftToRight,
+            workers,
+            |_, _, _| {
+                // This line has been emitted, but its credit isn't back yet
+                let in_flight = lines_read.load(Ordering::SeqCst) - emitted;
+                assert!(in_flight <= workers * 4, "{} lines in flight", in_flight);
+
+                emitted += 1;
+            },
+        );
+
+        assert_eq!(emitted, 10_000);
+        assert_eq!(totals, vec![30_000, 30_000]);
+    }
+
+    #[test]
+    fn test_stream_totals_do_not_overflow() {
+        let input = format!("{}: {}\n", usize::MAX, usize::MAX).repeat(3);
+
+        let totals = solve_stream(
+            input.as_bytes(),
+            &OPERATOR_SETS,
+            &Evaluation::LeftToRight,
+            2,
+            |_, _, _| {},
+        );
+
+        assert_eq!(totals, vec![usize::MAX as u128 * 3; 2]);
+    }
+}
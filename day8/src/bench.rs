@@ -0,0 +1,164 @@
+use std::{
+    collections::HashSet,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    find_antinodes, find_antinodes_parallel, iterate_pairs,
+    rule::{AntinodeRule, DistanceRatio, Harmonics, Resonance},
+    Puzzle, XY,
+};
+
+const FREQUENCIES: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// Time finding antinodes on the input and on large generated maps, comparing
+/// the original approach (rescanning the map for each frequency and
+/// collecting into a `HashSet`) with grouped frequencies and a bitset grid,
+/// sequentially and in parallel.
+pub fn run(input: &Puzzle, resonance: Resonance) {
+    let maps = [
+        ("input", input.clone()),
+        ("generated", generated(250, 1_000, 26)),
+        ("generated", generated(1_000, 5_000, 62)),
+        ("generated", generated(2_000, 10_000, 62)),
+    ];
+
+    let rules: [(&str, &dyn AntinodeRule); 2] = [
+        ("part one", &DistanceRatio::new(2)),
+        ("part two", &Harmonics::all(resonance)),
+    ];
+
+    for (name, puzzle) in maps.iter() {
+        println!(
+            "{} ({}x{}, {} antennas)",
+            name,
+            puzzle.dimensions[0],
+            puzzle.dimensions[1],
+            puzzle.data.len()
+        );
+
+        for (part, rule) in rules {
+            let (expected, rescanning) = time(|| find_antinodes_rescanning(puzzle, rule).len());
+            let (grouped, grid) = time(|| find_antinodes(puzzle, rule).len());
+            let (parallel, parallel_grid) = time(|| find_antinodes_parallel(puzzle, rule).len());
+
+            assert_eq!(grouped, expected, "Grid disagrees with rescanning");
+            assert_eq!(parallel, expected, "Parallel disagrees with rescanning");
+
+            println!(
+                "  {}: {} antinodes, rescanning {:?}, grid {:?}, parallel grid {:?}",
+                part, expected, rescanning, grid, parallel_grid
+            );
+        }
+    }
+}
+
+/// Generate a square map with antennas spread randomly across it. The same
+/// seed always generates the same map.
+pub fn generate(size: usize, antennas: usize, frequencies: usize, seed: u64) -> String {
+    assert!(antennas <= size * size, "Too many antennas for the map");
+    assert!(
+        (1..=FREQUENCIES.len()).contains(&frequencies),
+        "Invalid number of frequencies"
+    );
+
+    let mut rows = vec![vec![b'.'; size]; size];
+    let mut state = seed.max(1);
+
+    // xorshift64, good enough for spreading antennas around
+    let mut next = |bound: usize| {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        (state % bound as u64) as usize
+    };
+
+    let mut placed = 0;
+    while placed < antennas {
+        let (x, y) = (next(size), next(size));
+
+        if rows[y][x] == b'.' {
+            rows[y][x] = FREQUENCIES[next(frequencies)];
+            placed += 1;
+        }
+    }
+
+    rows.into_iter()
+        .map(|row| String::from_utf8(row).expect("Invalid map"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn generated(size: usize, antennas: usize, frequencies: usize) -> Puzzle {
+    Puzzle::from(generate(size, antennas, frequencies, size as u64).as_str())
+}
+
+/// The original approach: find the frequencies, then rescan the whole map for
+/// the antennas of each one.
+fn find_antinodes_rescanning(puzzle: &Puzzle, rule: &dyn AntinodeRule) -> HashSet<XY> {
+    let frequencies: HashSet<char> = puzzle.data.values().copied().collect();
+
+    frequencies
+        .iter()
+        .flat_map(|frequency| {
+            let positions: Vec<XY> = puzzle
+                .data
+                .iter()
+                .filter_map(|(pos, c)| if c == frequency { Some(*pos) } else { None })
+                .collect();
+
+            iterate_pairs(&positions)
+                .flat_map(|pair| rule.antinodes(puzzle, pair))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+fn time<T>(f: impl FnOnce() -> T) -> (T, Duration) {
+    let start = Instant::now();
+    let result = f();
+
+    (result, start.elapsed())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate() {
+        let map = generate(20, 50, 3, 7);
+
+        assert_eq!(map, generate(20, 50, 3, 7));
+        assert_ne!(map, generate(20, 50, 3, 8));
+
+        let puzzle: Puzzle = Puzzle::from(map.as_str());
+        assert_eq!(puzzle.dimensions, [20, 20]);
+        assert_eq!(puzzle.data.len(), 50);
+        assert!(puzzle.data.values().all(|c| "012".contains(*c)));
+    }
+
+    #[test]
+    fn test_approaches_agree_on_generated_maps() {
+        for seed in 1..5 {
+            let map = generate(60, 200, 8, seed);
+            let puzzle: Puzzle = Puzzle::from(map.as_str());
+
+            let rules: [Box<dyn AntinodeRule>; 3] = [
+                Box::new(DistanceRatio::new(2)),
+                Box::new(Harmonics::all(Resonance::Stepped)),
+                Box::new(Harmonics::all(Resonance::Collinear)),
+            ];
+
+            for rule in rules {
+                let expected = find_antinodes_rescanning(&puzzle, &rule);
+
+                let grid = find_antinodes(&puzzle, &rule);
+                assert_eq!(grid.iter().collect::<HashSet<_>>(), expected);
+
+                let parallel = find_antinodes_parallel(&puzzle, &rule);
+                assert_eq!(parallel, grid);
+            }
+        }
+    }
+}
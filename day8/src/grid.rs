@@ -0,0 +1,153 @@
+use crate::Pos;
+
+/// A set of positions within the dimensions of a Puzzle, stored as one bit
+/// per position. Much cheaper to fill than a `HashSet` when there are lots of
+/// antinodes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AntinodeGrid<const N: usize = 2> {
+    dimensions: Pos<N>,
+    bits: Vec<u64>,
+    len: usize,
+}
+
+impl<const N: usize> AntinodeGrid<N> {
+    pub fn new(dimensions: Pos<N>) -> Self {
+        let size: usize = dimensions.iter().product();
+
+        AntinodeGrid {
+            dimensions,
+            bits: vec![0; size.div_ceil(64)],
+            len: 0,
+        }
+    }
+
+    /// The number of positions in the grid.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Add a position, returning false if it was already there.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the position is outside the dimensions of the grid.
+    pub fn insert(&mut self, pos: Pos<N>) -> bool {
+        let (word, mask) = self.bit(&pos);
+
+        if self.bits[word] & mask != 0 {
+            return false;
+        }
+
+        self.bits[word] |= mask;
+        self.len += 1;
+
+        true
+    }
+
+    /// Add every position from another grid with the same dimensions.
+    pub fn union_with(&mut self, other: &AntinodeGrid<N>) {
+        assert_eq!(self.dimensions, other.dimensions, "Grid dimensions differ");
+
+        for (word, other) in self.bits.iter_mut().zip(other.bits.iter()) {
+            *word |= other;
+        }
+
+        self.len = self
+            .bits
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum();
+    }
+
+    /// Iterate over the positions in the grid, with the first axis changing
+    /// fastest, e.g. row by row in 2D.
+    pub fn iter(&self) -> impl Iterator<Item = Pos<N>> + '_ {
+        self.bits
+            .iter()
+            .enumerate()
+            .filter(|(_, word)| **word != 0)
+            .flat_map(move |(idx, &word)| {
+                (0..64)
+                    .filter(move |bit| word & (1 << bit) != 0)
+                    .map(move |bit| self.pos(idx * 64 + bit))
+            })
+    }
+
+    /// The word and bit mask for a position.
+    fn bit(&self, pos: &Pos<N>) -> (usize, u64) {
+        let mut idx = 0;
+
+        for axis in (0..N).rev() {
+            assert!(
+                pos[axis] < self.dimensions[axis],
+                "Position {:?} is outside the grid",
+                pos
+            );
+
+            idx = idx * self.dimensions[axis] + pos[axis];
+        }
+
+        (idx / 64, 1 << (idx % 64))
+    }
+
+    /// The position of a bit index.
+    fn pos(&self, mut idx: usize) -> Pos<N> {
+        self.dimensions.map(|dimension| {
+            let val = idx % dimension;
+            idx /= dimension;
+            val
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert() {
+        let mut grid = AntinodeGrid::new([10, 10]);
+
+        assert!(grid.insert([3, 7]));
+        assert!(!grid.insert([3, 7]));
+        assert!(grid.insert([9, 9]));
+
+        assert_eq!(grid.len(), 2);
+        assert_eq!(grid.iter().collect::<Vec<_>>(), vec![[3, 7], [9, 9]]);
+    }
+
+    #[test]
+    fn test_iter_in_row_order() {
+        let mut grid = AntinodeGrid::new([3, 2, 2]);
+        grid.insert([0, 1, 1]);
+        grid.insert([2, 0, 0]);
+        grid.insert([1, 1, 0]);
+
+        assert_eq!(
+            grid.iter().collect::<Vec<_>>(),
+            vec![[2, 0, 0], [1, 1, 0], [0, 1, 1]]
+        );
+    }
+
+    #[test]
+    fn test_union_with() {
+        let mut a = AntinodeGrid::new([100, 100]);
+        a.insert([0, 0]);
+        a.insert([50, 50]);
+
+        let mut b = AntinodeGrid::new([100, 100]);
+        b.insert([50, 50]);
+        b.insert([99, 99]);
+
+        a.union_with(&b);
+
+        assert_eq!(a.len(), 3);
+        assert_eq!(a.iter().last(), Some([99, 99]));
+    }
+
+    #[test]
+    #[should_panic(expected = "outside the grid")]
+    fn test_insert_outside_the_grid() {
+        AntinodeGrid::new([4, 4]).insert([4, 0]);
+    }
+}
@@ -0,0 +1,301 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::{render::Overlay, rule::AntinodeRule, Pos, Puzzle};
+
+/// Keeps the antinodes of a Puzzle up to date as antennas are added, moved
+/// and removed, without recomputing every pair.
+///
+/// Each antinode position is reference counted by the number of antenna
+/// pairs that create it, so it only disappears once the last of those pairs
+/// is broken up.
+pub struct IncrementalAntinodes<R, const N: usize = 2> {
+    puzzle: Puzzle<N>,
+    rule: R,
+
+    // The positions of the antennas for each frequency
+    frequencies: HashMap<char, HashSet<Pos<N>>>,
+
+    // The number of antenna pairs of each frequency creating an antinode at
+    // each position
+    pair_counts: HashMap<Pos<N>, BTreeMap<char, usize>>,
+}
+
+impl<R: AntinodeRule<N>, const N: usize> IncrementalAntinodes<R, N> {
+    pub fn new(puzzle: Puzzle<N>, rule: R) -> Self {
+        let antennas: Vec<(Pos<N>, char)> = puzzle.data.iter().map(|(&pos, &c)| (pos, c)).collect();
+
+        let mut incremental = IncrementalAntinodes {
+            puzzle: Puzzle {
+                dimensions: puzzle.dimensions,
+                data: HashMap::new(),
+            },
+            rule,
+            frequencies: HashMap::new(),
+            pair_counts: HashMap::new(),
+        };
+
+        for (pos, frequency) in antennas {
+            incremental.insert(pos, frequency);
+        }
+
+        incremental
+    }
+
+    /// The number of distinct antinode positions.
+    pub fn len(&self) -> usize {
+        self.pair_counts.len()
+    }
+
+    /// The number of antenna pairs creating an antinode at a position.
+    pub fn pair_count(&self, pos: &Pos<N>) -> usize {
+        self.pair_counts
+            .get(pos)
+            .map_or(0, |counts| counts.values().sum())
+    }
+
+    /// The current antinodes of each frequency, e.g. for rendering.
+    pub fn overlay(&self) -> Overlay<N> {
+        let mut overlay = Overlay::new();
+
+        for (pos, counts) in self.pair_counts.iter() {
+            for frequency in counts.keys() {
+                overlay.entry(*frequency).or_default().insert(*pos);
+            }
+        }
+
+        overlay
+    }
+
+    pub fn puzzle(&self) -> &Puzzle<N> {
+        &self.puzzle
+    }
+
+    /// Place an antenna, replacing any antenna already at that position.
+    /// Returns the frequency of the replaced antenna.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the position is outside the dimensions of the Puzzle.
+    pub fn insert(&mut self, pos: Pos<N>, frequency: char) -> Option<char> {
+        assert!(
+            pos.iter()
+                .zip(self.puzzle.dimensions)
+                .all(|(val, dim)| *val < dim),
+            "Antenna {:?} is outside the Puzzle",
+            pos
+        );
+
+        let replaced = self.remove(pos);
+
+        let others = self.frequencies.entry(frequency).or_default();
+        let pairs: Vec<(Pos<N>, Pos<N>)> = others.iter().map(|&other| (other, pos)).collect();
+        others.insert(pos);
+
+        for pair in pairs {
+            self.update_pair(frequency, pair, true);
+        }
+
+        self.puzzle.data.insert(pos, frequency);
+
+        replaced
+    }
+
+    /// Remove the antenna at a position, returning its frequency.
+    pub fn remove(&mut self, pos: Pos<N>) -> Option<char> {
+        let frequency = self.puzzle.data.remove(&pos)?;
+
+        let others = self
+            .frequencies
+            .get_mut(&frequency)
+            .expect("Antenna missing from its frequency");
+        others.remove(&pos);
+
+        let pairs: Vec<(Pos<N>, Pos<N>)> = others.iter().map(|&other| (other, pos)).collect();
+        if others.is_empty() {
+            self.frequencies.remove(&frequency);
+        }
+
+        for pair in pairs {
+            self.update_pair(frequency, pair, false);
+        }
+
+        Some(frequency)
+    }
+
+    /// Move the antenna at one position to another, replacing any antenna
+    /// already there. Returns false if there is no antenna to move.
+    pub fn move_antenna(&mut self, from: Pos<N>, to: Pos<N>) -> bool {
+        match self.remove(from) {
+            Some(frequency) => {
+                self.insert(to, frequency);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Add or remove the antinodes created by a single pair of antennas.
+    fn update_pair(&mut self, frequency: char, (a, b): (Pos<N>, Pos<N>), add: bool) {
+        // Always ask the rule about the pair in the same order, so that the
+        // antinodes removed are exactly the ones that were added.
+        let pair = if a <= b { (&a, &b) } else { (&b, &a) };
+
+        let antinodes: HashSet<Pos<N>> = self
+            .rule
+            .antinodes(&self.puzzle, pair)
+            .into_iter()
+            .collect();
+
+        for pos in antinodes {
+            let counts = self.pair_counts.entry(pos).or_default();
+
+            if add {
+                *counts.entry(frequency).or_insert(0) += 1;
+                continue;
+            }
+
+            let count = counts
+                .get_mut(&frequency)
+                .expect("Removing an antinode that was never added");
+            *count -= 1;
+
+            if *count == 0 {
+                counts.remove(&frequency);
+            }
+
+            if counts.is_empty() {
+                self.pair_counts.remove(&pos);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::{
+        find_antinodes, find_antinodes_by_frequency,
+        rule::{DistanceRatio, Harmonics, Resonance},
+        tests::INPUT,
+        XY,
+    };
+
+    fn antinodes<R: AntinodeRule>(incremental: &IncrementalAntinodes<R>) -> HashSet<XY> {
+        incremental.overlay().into_values().flatten().collect()
+    }
+
+    #[test]
+    fn test_matches_full_computation() {
+        let incremental =
+            IncrementalAntinodes::<_, 2>::new(Puzzle::from(INPUT), DistanceRatio::new(2));
+        assert_eq!(incremental.len(), 14);
+
+        let incremental = IncrementalAntinodes::<_, 2>::new(
+            Puzzle::from(INPUT),
+            Harmonics::all(Resonance::Stepped),
+        );
+        assert_eq!(incremental.len(), 34);
+    }
+
+    #[test]
+    fn test_insert_and_remove() {
+        let mut incremental =
+            IncrementalAntinodes::<_, 2>::new(Puzzle::from(INPUT), DistanceRatio::new(2));
+
+        assert_eq!(incremental.insert([3, 3], 'A'), None);
+        let expected = find_antinodes(incremental.puzzle(), &DistanceRatio::new(2));
+        assert_eq!(antinodes(&incremental), expected.iter().collect());
+
+        assert_eq!(incremental.remove([3, 3]), Some('A'));
+        assert_eq!(incremental.len(), 14);
+
+        assert_eq!(incremental.remove([3, 3]), None);
+    }
+
+    #[test]
+    fn test_shared_antinodes_are_reference_counted() {
+        // Both pairs create an antinode at (0, 0)
+        let puzzle: Puzzle = Puzzle::from("..a.a.\n......\nb.....\n......\nb.....\n......");
+        let mut incremental = IncrementalAntinodes::new(puzzle, DistanceRatio::new(2));
+
+        assert_eq!(incremental.pair_count(&[0, 0]), 2);
+
+        incremental.remove([0, 4]);
+        assert_eq!(incremental.pair_count(&[0, 0]), 1);
+        assert_eq!(incremental.len(), 1);
+
+        incremental.remove([4, 0]);
+        assert_eq!(incremental.pair_count(&[0, 0]), 0);
+        assert_eq!(incremental.len(), 0);
+    }
+
+    #[test]
+    fn test_move_and_replace() {
+        let puzzle: Puzzle = Puzzle::from("a.a...\n......\n......");
+        let mut incremental = IncrementalAntinodes::new(puzzle, DistanceRatio::new(2));
+        assert_eq!(antinodes(&incremental), HashSet::from([[4, 0]]));
+
+        assert!(incremental.move_antenna([2, 0], [1, 0]));
+        assert_eq!(antinodes(&incremental), HashSet::from([[2, 0]]));
+
+        assert!(!incremental.move_antenna([5, 2], [1, 1]));
+
+        // Replacing an antenna with another frequency breaks up the pair
+        assert_eq!(incremental.insert([1, 0], 'b'), Some('a'));
+        assert_eq!(incremental.len(), 0);
+    }
+
+    #[derive(Clone, Debug)]
+    enum Edit {
+        Insert(XY, char),
+        Remove(XY),
+        Move(XY, XY),
+    }
+
+    fn edit() -> impl Strategy<Value = Edit> {
+        let pos = [0..8usize, 0..8usize];
+        let frequency = prop::sample::select(vec!['a', 'b', 'c']);
+
+        prop_oneof![
+            (pos.clone(), frequency).prop_map(|(pos, c)| Edit::Insert(pos, c)),
+            pos.clone().prop_map(Edit::Remove),
+            (pos.clone(), pos).prop_map(|(from, to)| Edit::Move(from, to)),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn edits_match_full_recomputation(edits in prop::collection::vec(edit(), 1..40)) {
+            let empty = "........\n".repeat(8);
+            let rules: [Box<dyn AntinodeRule>; 3] = [
+                Box::new(DistanceRatio::new(2)),
+                Box::new(DistanceRatio::new(3).with_inner_points()),
+                Box::new(Harmonics::all(Resonance::Collinear)),
+            ];
+
+            for rule in rules {
+                let mut incremental = IncrementalAntinodes::new(Puzzle::from(empty.as_str()), rule);
+
+                for edit in edits.iter() {
+                    match *edit {
+                        Edit::Insert(pos, c) => {
+                            incremental.insert(pos, c);
+                        }
+                        Edit::Remove(pos) => {
+                            incremental.remove(pos);
+                        }
+                        Edit::Move(from, to) => {
+                            incremental.move_antenna(from, to);
+                        }
+                    }
+
+                    let expected = find_antinodes_by_frequency(incremental.puzzle(), &incremental.rule);
+                    let expected: Overlay = expected.into_iter().filter(|(_, antinodes)| !antinodes.is_empty()).collect();
+                    prop_assert_eq!(incremental.overlay(), expected);
+                }
+            }
+        }
+    }
+}
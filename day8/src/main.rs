@@ -1,50 +1,343 @@
-use std::collections::{HashMap, HashSet};
+mod bench;
+mod grid;
+mod incremental;
+mod provenance;
+mod render;
+mod rule;
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+};
+
+use grid::AntinodeGrid;
+use incremental::IncrementalAntinodes;
+use provenance::Provenance;
+use rayon::prelude::*;
+use render::Overlay;
+use rule::{AntinodeRule, DistanceRatio, Harmonics, MaxRange, Resonance};
 
 fn main() {
     let input = shared::read_file_from_args();
-    let puzzle = Puzzle::from(input.as_str());
 
-    let part_one = part_one(&puzzle);
-    println!("part one: {}", part_one);
+    let resonance = if shared::has_flag("--collinear") {
+        Resonance::Collinear
+    } else {
+        Resonance::Stepped
+    };
+
+    // Voxel maps are stacked layers separated by blank lines. Drawing them
+    // isn't supported, but everything else works the same as in 2D.
+    if shared::has_flag("--3d") {
+        let puzzle = Puzzle::<3>::from(input.as_str());
+        solve(&puzzle, resonance);
+
+        if let Some(filename) = shared::flag_value("--edits") {
+            let edits = fs::read_to_string(filename).expect("Failed to read edits");
+            apply_edits(puzzle, resonance, &edits);
+        }
+
+        return;
+    }
+
+    let puzzle = Puzzle::<2>::from(input.as_str());
+
+    if shared::has_flag("--bench") {
+        bench::run(&puzzle, resonance);
+        return;
+    }
+
+    solve(&puzzle, resonance);
+    print_overlays(&puzzle, resonance);
 
-    let part_two = part_two(&puzzle);
+    if let Some(filename) = shared::flag_value("--edits") {
+        let edits = fs::read_to_string(filename).expect("Failed to read edits");
+        let (part_one, part_two) = apply_edits(puzzle, resonance, &edits);
+
+        if shared::has_flag("--render") {
+            let colour = shared::has_flag("--colour");
+
+            println!(
+                "\n{}",
+                render::render(part_one.puzzle(), &part_one.overlay(), colour)
+            );
+            println!(
+                "\n{}",
+                render::render(part_two.puzzle(), &part_two.overlay(), colour)
+            );
+        }
+    }
+}
+
+/// Print the answers for both parts, along with any custom rule and
+/// explanations asked for on the command line.
+fn solve<const N: usize>(puzzle: &Puzzle<N>, resonance: Resonance) {
+    let (part_one, part_two) = if shared::has_flag("--parallel") {
+        (
+            find_antinodes_parallel(puzzle, &DistanceRatio::new(2)).len(),
+            find_antinodes_parallel(puzzle, &Harmonics::all(resonance)).len(),
+        )
+    } else {
+        (part_one(puzzle), part_two(puzzle, resonance))
+    };
+
+    println!("part one: {}", part_one);
     println!("part one: {}", part_two);
+
+    if let Some(rule) = rule_from_args(resonance) {
+        println!(
+            "custom rule: {}",
+            find_antinodes(puzzle, rule.as_ref()).len()
+        );
+    }
+
+    if shared::has_flag("--explain") {
+        explain(puzzle, resonance);
+    }
 }
 
-fn part_one(puzzle: &Puzzle) -> usize {
+/// Apply a list of antenna edits, printing the updated counts after each one.
+/// Each line is one of:
+///
+/// ```text
+/// + 3,4 a   (place an `a` antenna at (3, 4))
+/// - 3,4     (remove the antenna at (3, 4))
+/// > 3,4 5,6 (move the antenna at (3, 4) to (5, 6))
+/// ? 3,4     (count the antenna pairs creating an antinode at (3, 4))
+/// ```
+///
+/// Positions have one value per dimension of the Puzzle, e.g. `3,4,1` in 3D.
+fn apply_edits<const N: usize>(
+    puzzle: Puzzle<N>,
+    resonance: Resonance,
+    edits: &str,
+) -> (
+    IncrementalAntinodes<DistanceRatio, N>,
+    IncrementalAntinodes<Harmonics, N>,
+) {
+    let mut part_one = IncrementalAntinodes::new(puzzle.clone(), DistanceRatio::new(2));
+    let mut part_two = IncrementalAntinodes::new(puzzle, Harmonics::all(resonance));
+
+    for line in edits.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        let pieces: Vec<&str> = line.split_whitespace().collect();
+
+        match pieces.as_slice() {
+            ["+", pos, frequency] => {
+                let frequency = frequency.chars().next().expect("Invalid frequency");
+                part_one.insert(parse_pos(pos), frequency);
+                part_two.insert(parse_pos(pos), frequency);
+            }
+            ["-", pos] => {
+                part_one.remove(parse_pos(pos));
+                part_two.remove(parse_pos(pos));
+            }
+            [">", from, to] => {
+                part_one.move_antenna(parse_pos(from), parse_pos(to));
+                part_two.move_antenna(parse_pos(from), parse_pos(to));
+            }
+            ["?", pos] => {
+                println!(
+                    "{}: part one: {} pairs, part two: {} pairs",
+                    line,
+                    part_one.pair_count(&parse_pos(pos)),
+                    part_two.pair_count(&parse_pos(pos))
+                );
+                continue;
+            }
+            _ => panic!("Invalid edit: {}", line),
+        }
+
+        println!(
+            "{}: part one: {}, part two: {}",
+            line,
+            part_one.len(),
+            part_two.len()
+        );
+    }
+
+    (part_one, part_two)
+}
+
+/// Explain the antinode count for each part by frequency, and optionally list
+/// the antenna pairs creating the antinode at `--explain-at=x,y`.
+fn explain<const N: usize>(puzzle: &Puzzle<N>, resonance: Resonance) {
+    let parts: [(&str, &dyn AntinodeRule<N>); 2] = [
+        ("part one", &DistanceRatio::new(2)),
+        ("part two", &Harmonics::all(resonance)),
+    ];
+
+    let at = shared::flag_value("--explain-at").map(|pos| parse_pos::<N>(&pos));
+
+    for (part, rule) in parts {
+        let provenance = Provenance::new(puzzle, rule);
+        println!("\n{}: {} antinodes", part, provenance.len());
+
+        for (frequency, summary) in provenance.frequency_summary() {
+            println!(
+                "  {}: {} antinodes, {} shared with other frequencies",
+                frequency, summary.antinodes, summary.shared
+            );
+        }
+
+        if let Some(pos) = at {
+            println!("  at {:?}:", pos);
+
+            for source in provenance.sources_at(&pos) {
+                println!(
+                    "    {} antennas at {:?} and {:?}",
+                    source.frequency, source.pair.0, source.pair.1
+                );
+            }
+        }
+    }
+}
+
+/// Draw the antinodes for each part, or compare them against maps pasted from
+/// the puzzle text.
+fn print_overlays(puzzle: &Puzzle, resonance: Resonance) {
+    let parts: [(&str, &dyn AntinodeRule); 2] = [
+        ("part-one", &DistanceRatio::new(2)),
+        ("part-two", &Harmonics::all(resonance)),
+    ];
+
+    for (part, rule) in parts {
+        if shared::has_flag("--render") {
+            let overlay = find_antinodes_by_frequency(puzzle, rule);
+            let colour = shared::has_flag("--colour");
+
+            println!("\n{}", render::render(puzzle, &overlay, colour));
+        }
+
+        if let Some(filename) = shared::flag_value(&format!("--diff-{}", part)) {
+            let expected = fs::read_to_string(filename).expect("Failed to read expected map");
+            let diff = render::diff(&expected, &find_antinodes(puzzle, rule));
+
+            if diff.is_empty() {
+                println!("{}: matches", part);
+            } else {
+                println!(
+                    "{}: missing {:?}, unexpected {:?}",
+                    part, diff.missing, diff.unexpected
+                );
+            }
+        }
+    }
+}
+
+/// Parse a position from the command line, e.g. `3,4` in 2D.
+fn parse_pos<const N: usize>(pos: &str) -> Pos<N> {
+    let values: Vec<usize> = pos
+        .split(',')
+        .map(|val| val.trim().parse().expect("Invalid position"))
+        .collect();
+
+    values
+        .try_into()
+        .unwrap_or_else(|_| panic!("Expected {} values in position {}", N, pos))
+}
+
+fn part_one<const N: usize>(puzzle: &Puzzle<N>) -> usize {
+    find_antinodes(puzzle, &DistanceRatio::new(2)).len()
+}
+
+fn part_two<const N: usize>(puzzle: &Puzzle<N>, resonance: Resonance) -> usize {
+    find_antinodes(puzzle, &Harmonics::all(resonance)).len()
+}
+
+/// Find the antinodes created by every pair of antennas with the same
+/// frequency.
+fn find_antinodes<const N: usize>(
+    puzzle: &Puzzle<N>,
+    rule: &dyn AntinodeRule<N>,
+) -> AntinodeGrid<N> {
+    let mut grid = AntinodeGrid::new(puzzle.dimensions);
+
+    for positions in puzzle.antennas_by_frequency().values() {
+        for pair in iterate_pairs(positions) {
+            for pos in rule.antinodes(puzzle, pair) {
+                grid.insert(pos);
+            }
+        }
+    }
+
+    grid
+}
+
+/// The same as `find_antinodes`, but with each frequency processed on its own
+/// thread. Only worth it for large maps with lots of frequencies.
+fn find_antinodes_parallel<const N: usize>(
+    puzzle: &Puzzle<N>,
+    rule: &dyn AntinodeRule<N>,
+) -> AntinodeGrid<N> {
     puzzle
-        .antenna_types()
-        .iter()
-        .fold(HashSet::new(), |mut acc, antenna_type| {
-            let positions = puzzle.all_coords_for_antenna(*antenna_type);
-
-            iterate_pairs(&positions)
-                .flat_map(|pair| antinodes_for_pair(puzzle, pair))
-                .for_each(|pos| {
-                    acc.insert(pos);
-                });
-
-            acc
+        .antennas_by_frequency()
+        .into_par_iter()
+        .map(|(_, positions)| {
+            let mut grid = AntinodeGrid::new(puzzle.dimensions);
+
+            for pair in iterate_pairs(&positions) {
+                for pos in rule.antinodes(puzzle, pair) {
+                    grid.insert(pos);
+                }
+            }
+
+            grid
         })
-        .len()
+        .reduce(
+            || AntinodeGrid::new(puzzle.dimensions),
+            |mut acc, grid| {
+                acc.union_with(&grid);
+                acc
+            },
+        )
 }
 
-fn part_two(puzzle: &Puzzle) -> usize {
+/// Find the antinodes created by each antenna frequency.
+fn find_antinodes_by_frequency<const N: usize>(
+    puzzle: &Puzzle<N>,
+    rule: &dyn AntinodeRule<N>,
+) -> Overlay<N> {
     puzzle
-        .antenna_types()
-        .iter()
-        .fold(HashSet::new(), |mut acc, antenna_type| {
-            let positions = puzzle.all_coords_for_antenna(*antenna_type);
-
-            iterate_pairs(&positions)
-                .flat_map(|pair| antinodes_for_pair_with_resonant_freqs(puzzle, pair))
-                .for_each(|pos| {
-                    acc.insert(pos);
-                });
-
-            acc
+        .antennas_by_frequency()
+        .into_iter()
+        .map(|(frequency, positions)| {
+            let antinodes = iterate_pairs(&positions)
+                .flat_map(|pair| rule.antinodes(puzzle, pair))
+                .collect();
+
+            (frequency, antinodes)
         })
-        .len()
+        .collect()
+}
+
+/// Build a custom rule from the command line, e.g. `--ratio=3 --inner` or
+/// `--harmonics=2,3 --max-range=10`.
+fn rule_from_args<const N: usize>(resonance: Resonance) -> Option<Box<dyn AntinodeRule<N>>> {
+    let rule: Box<dyn AntinodeRule<N>> = if let Some(ratio) = shared::flag_value("--ratio") {
+        let rule = DistanceRatio::new(ratio.parse().expect("Invalid ratio"));
+
+        if shared::has_flag("--inner") {
+            Box::new(rule.with_inner_points())
+        } else {
+            Box::new(rule)
+        }
+    } else if let Some(multiples) = shared::flag_value("--harmonics") {
+        let multiples = multiples
+            .split(',')
+            .map(|multiple| multiple.parse().expect("Invalid harmonic"));
+
+        Box::new(Harmonics::only(multiples, resonance))
+    } else {
+        return None;
+    };
+
+    match shared::flag_value("--max-range") {
+        Some(max_range) => Some(Box::new(MaxRange::new(
+            rule,
+            max_range.parse().expect("Invalid max range"),
+        ))),
+        None => Some(rule),
+    }
 }
 
 /// Create an iterator that yields all possible pairs of items in a Vec/array.
@@ -70,160 +363,105 @@ fn iterate_pairs<'a, T>(positions: &'a [T]) -> impl Iterator<Item = (&'a T, &'a
     })
 }
 
-/// Find all positions that are exactly twice the distance from one point as they are from the
-/// other point. Antinodes cannot exist outside the dimensions of the Puzzle.
-///
-/// # Example
-///
-/// `#` represents the antinodes for antenna `a`.
-///
-/// ..........
-/// ...#......
-/// #.........
-/// ....a.....
-/// ........a.
-/// .....a....
-/// ..#.......
-/// ......#...
-/// ..........
-/// ..........
-fn antinodes_for_pair(puzzle: &Puzzle, pair: (&XY, &XY)) -> Vec<XY> {
-    let ((x1, y1), (x2, y2)) = pair;
-
-    let dx = *x2 as isize - *x1 as isize;
-    let dy = *y2 as isize - *y1 as isize;
-
-    let mut antinodes = Vec::new();
-
-    let x3 = *x1 as isize - dx;
-    let y3 = *y1 as isize - dy;
-
-    if x3 >= 0 && y3 >= 0 && x3 < puzzle.dimensions.0 as isize && y3 < puzzle.dimensions.1 as isize
-    {
-        antinodes.push((x3 as usize, y3 as usize));
-    }
+/// A position with one value per dimension, e.g. `[x, y]` or `[x, y, z]`.
+type Pos<const N: usize> = [usize; N];
 
-    let x4 = *x2 as isize + dx;
-    let y4 = *y2 as isize + dy;
+type XY = Pos<2>;
 
-    if x4 >= 0 && y4 >= 0 && x4 < puzzle.dimensions.0 as isize && y4 < puzzle.dimensions.1 as isize
-    {
-        antinodes.push((x4 as usize, y4 as usize));
-    }
+#[derive(Clone, Debug)]
+struct Puzzle<const N: usize = 2> {
+    dimensions: Pos<N>,
 
-    antinodes
+    // A map of positions to the antenna type (represented by a char)
+    data: HashMap<Pos<N>, char>,
 }
 
-/// Find all positions that are an interval of the distance between two points.
-///
-/// # Example
-///
-/// `#` represents the antinodes for antenna `T`.
-///
-/// T....#....
-/// ...T......
-/// .T....#...
-/// .........#
-/// ..#.......
-/// ..........
-/// ...#......
-/// ..........
-/// ....#.....
-/// ..........
-fn antinodes_for_pair_with_resonant_freqs(puzzle: &Puzzle, pair: (&XY, &XY)) -> Vec<XY> {
-    let ((x1, y1), (x2, y2)) = pair;
-
-    let dx = *x2 as isize - *x1 as isize;
-    let dy = *y2 as isize - *y1 as isize;
-
-    // Antinodes include the antennae themselves
-    let mut antinodes = Vec::from([(*x1, *y1), (*x2, *y2)]);
-
-    let mut x3 = *x1 as isize;
-    let mut y3 = *y1 as isize;
-
-    // Moving away from point 1
-    loop {
-        x3 -= dx;
-        y3 -= dy;
-
-        if x3 < 0
-            || y3 < 0
-            || x3 >= puzzle.dimensions.0 as isize
-            || y3 >= puzzle.dimensions.1 as isize
-        {
-            break;
-        }
-
-        antinodes.push((x3 as usize, y3 as usize));
-    }
-
-    // Moving away from point 2
-    x3 = *x2 as isize;
-    y3 = *y2 as isize;
+impl<const N: usize> Puzzle<N> {
+    /// Convert a signed position to a Puzzle position, if it is within the
+    /// dimensions of the Puzzle.
+    fn checked_pos(&self, pos: [isize; N]) -> Option<Pos<N>> {
+        let mut checked = [0; N];
 
-    loop {
-        x3 += dx;
-        y3 += dy;
+        for axis in 0..N {
+            if pos[axis] < 0 || pos[axis] >= self.dimensions[axis] as isize {
+                return None;
+            }
 
-        if x3 < 0
-            || y3 < 0
-            || x3 >= puzzle.dimensions.0 as isize
-            || y3 >= puzzle.dimensions.1 as isize
-        {
-            break;
+            checked[axis] = pos[axis] as usize;
         }
 
-        antinodes.push((x3 as usize, y3 as usize));
+        Some(checked)
     }
 
-    antinodes
-}
+    /// Group the antenna positions by frequency in a single pass. Positions
+    /// are sorted, so pairs are always visited in the same order.
+    fn antennas_by_frequency(&self) -> BTreeMap<char, Vec<Pos<N>>> {
+        let mut frequencies: BTreeMap<char, Vec<Pos<N>>> = BTreeMap::new();
 
-type XY = (usize, usize);
-
-#[derive(Debug)]
-struct Puzzle {
-    dimensions: XY,
-
-    // A map of (x, y) to the antenna type (represented by a char)
-    data: HashMap<XY, char>,
-}
+        for (pos, frequency) in self.data.iter() {
+            frequencies.entry(*frequency).or_default().push(*pos);
+        }
 
-impl Puzzle {
-    // Get the unique antenna types in the puzzle
-    fn antenna_types(&self) -> HashSet<char> {
-        self.data.values().copied().collect()
-    }
+        for positions in frequencies.values_mut() {
+            positions.sort_unstable();
+        }
 
-    fn all_coords_for_antenna(&self, antenna: char) -> Vec<XY> {
-        self.data
-            .iter()
-            .filter_map(|(pos, c)| if *c == antenna { Some(*pos) } else { None })
-            .collect()
+        frequencies
     }
 }
 
-impl From<&str> for Puzzle {
-    fn from(input: &str) -> Puzzle {
-        let lines = input.trim().lines().map(str::trim);
-
-        let width = lines.clone().map(str::len).max().unwrap();
-        let height = lines.clone().count();
-
-        let data = lines
-            .enumerate()
-            .flat_map(|(y, line)| line.chars().enumerate().map(move |(x, c)| ((x, y), c)))
-            .filter(|(_, c)| *c != '.')
-            .fold(HashMap::new(), |mut acc, (pos, c)| {
-                acc.insert(pos, c);
-                acc
-            });
-
-        Puzzle {
-            dimensions: (width, height),
-            data,
+/// Parse a map of rows. Beyond two dimensions, each extra dimension is
+/// separated by one more blank line than the one before it: in 3D, layers are
+/// separated by a single blank line. For example, a 3x2x2 map:
+///
+/// ```text
+/// a..
+/// ...
+///
+/// ..a
+/// ...
+/// ```
+impl<const N: usize> From<&str> for Puzzle<N> {
+    fn from(input: &str) -> Puzzle<N> {
+        assert!(N >= 2, "A Puzzle needs at least two dimensions");
+
+        let mut dimensions = [0; N];
+        let mut data = HashMap::new();
+
+        let mut pos = [0; N];
+        let mut blank_lines = 0;
+
+        for (idx, line) in input.trim().lines().map(str::trim).enumerate() {
+            // In 2D, blank lines are just empty rows
+            if line.is_empty() && N > 2 {
+                blank_lines += 1;
+                continue;
+            }
+
+            if idx > 0 {
+                // Move to the next row, or to the next layer after blank lines
+                let axis = (blank_lines + 1).min(N - 1);
+                pos[axis] += 1;
+                pos[1..axis].fill(0);
+            }
+
+            blank_lines = 0;
+
+            for (x, c) in line.chars().enumerate() {
+                pos[0] = x;
+
+                if c != '.' {
+                    data.insert(pos, c);
+                }
+            }
+
+            dimensions[0] = dimensions[0].max(line.len());
+            for axis in 1..N {
+                dimensions[axis] = dimensions[axis].max(pos[axis] + 1);
+            }
         }
+
+        Puzzle { dimensions, data }
     }
 }
 
@@ -231,7 +469,7 @@ impl From<&str> for Puzzle {
 mod tests {
     use super::*;
 
-    const INPUT: &str = r#"
+    pub(crate) const INPUT: &str = r#"
         ............
         ........0...
         .....0......
@@ -248,7 +486,7 @@ mod tests {
 
     #[test]
     fn test_part_one() {
-        let puzzle = Puzzle::from(INPUT);
+        let puzzle: Puzzle = Puzzle::from(INPUT);
         let result = part_one(&puzzle);
 
         assert_eq!(result, 14);
@@ -256,9 +494,160 @@ mod tests {
 
     #[test]
     fn test_part_two() {
-        let puzzle = Puzzle::from(INPUT);
-        let result = part_two(&puzzle);
+        let puzzle: Puzzle = Puzzle::from(INPUT);
+        let result = part_two(&puzzle, Resonance::Stepped);
 
         assert_eq!(result, 34);
     }
+
+    #[test]
+    fn test_part_two_collinear() {
+        // No pair in the example has a common divisor in its distance, so
+        // the two modes agree.
+        let puzzle: Puzzle = Puzzle::from(INPUT);
+        let result = part_two(&puzzle, Resonance::Collinear);
+
+        assert_eq!(result, 34);
+    }
+
+    #[test]
+    fn test_collinear_includes_positions_between_steps() {
+        let puzzle: Puzzle = Puzzle::from(
+            r#"
+            a.....
+            ......
+            ..a...
+            ......
+            ......
+            ......
+            "#,
+        );
+
+        let stepped = find_antinodes(&puzzle, &Harmonics::all(Resonance::Stepped));
+        assert_eq!(stepped.iter().collect::<Vec<_>>(), [[0, 0], [2, 2], [4, 4]]);
+
+        let collinear = find_antinodes(&puzzle, &Harmonics::all(Resonance::Collinear));
+        assert_eq!(
+            collinear.iter().collect::<Vec<_>>(),
+            [[0, 0], [1, 1], [2, 2], [3, 3], [4, 4], [5, 5]]
+        );
+
+        assert_eq!(part_two(&puzzle, Resonance::Stepped), 3);
+        assert_eq!(part_two(&puzzle, Resonance::Collinear), 6);
+    }
+
+    #[test]
+    fn test_collinear_with_different_step_on_each_axis() {
+        let puzzle: Puzzle = Puzzle::from(
+            r#"
+            ......
+            .a....
+            ......
+            ......
+            ......
+            ...a..
+            ......
+            "#,
+        );
+
+        // The antennas are (2, 4) apart, so the collinear step is (1, 2)
+        // and finds (2, 3) halfway between them.
+        assert_eq!(part_two(&puzzle, Resonance::Stepped), 2);
+        assert_eq!(part_two(&puzzle, Resonance::Collinear), 3);
+    }
+
+    #[test]
+    fn test_collinear_on_a_vertical_line() {
+        let puzzle: Puzzle = Puzzle::from(
+            r#"
+            ...
+            .b.
+            ...
+            .b.
+            ...
+            "#,
+        );
+
+        assert_eq!(part_two(&puzzle, Resonance::Stepped), 2);
+        assert_eq!(part_two(&puzzle, Resonance::Collinear), 5);
+    }
+
+    #[test]
+    fn test_parts_are_configurations_of_one_engine() {
+        let puzzle: Puzzle = Puzzle::from(INPUT);
+
+        // Part one is the 1st harmonic only, part two is every harmonic
+        let first_harmonic = Harmonics::only([1], Resonance::Stepped);
+        assert_eq!(find_antinodes(&puzzle, &first_harmonic).len(), 14);
+
+        // Limiting the range to the whole map changes nothing
+        let limited = MaxRange::new(Harmonics::all(Resonance::Stepped), 12);
+        assert_eq!(find_antinodes(&puzzle, &limited).len(), 34);
+    }
+
+    const VOXEL_INPUT: &str = r#"
+        a...
+        ....
+        ....
+        ....
+
+        ....
+        .a..
+        ....
+        ..b.
+
+        ....
+        ....
+        ....
+        ....
+
+        ....
+        ....
+        ....
+        ..b.
+    "#;
+
+    #[test]
+    fn test_parse_layers() {
+        let puzzle: Puzzle<3> = Puzzle::from(VOXEL_INPUT);
+
+        assert_eq!(puzzle.dimensions, [4, 4, 4]);
+        assert_eq!(
+            puzzle.data,
+            HashMap::from([
+                ([0, 0, 0], 'a'),
+                ([1, 1, 1], 'a'),
+                ([2, 3, 1], 'b'),
+                ([2, 3, 3], 'b'),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_extra_dimensions() {
+        // Two blank lines move along the 4th axis, resetting the 3rd
+        let puzzle: Puzzle<4> = Puzzle::from("a.\n\n.a\n\n\nb.\n\n.b");
+
+        assert_eq!(puzzle.dimensions, [2, 1, 2, 2]);
+        assert_eq!(puzzle.data[&[1, 0, 1, 0]], 'a');
+        assert_eq!(puzzle.data[&[0, 0, 0, 1]], 'b');
+        assert_eq!(puzzle.data[&[1, 0, 1, 1]], 'b');
+    }
+
+    #[test]
+    fn test_antinodes_across_layers() {
+        let puzzle: Puzzle<3> = Puzzle::from(VOXEL_INPUT);
+
+        // `b` would need a 5th layer, so only `a` creates an antinode
+        assert_eq!(
+            find_antinodes(&puzzle, &DistanceRatio::new(2))
+                .iter()
+                .collect::<Vec<_>>(),
+            [[2, 2, 2]]
+        );
+        assert_eq!(part_one(&puzzle), 1);
+
+        // The `a` diagonal plus the `b` column
+        assert_eq!(part_two(&puzzle, Resonance::Stepped), 6);
+    }
 }
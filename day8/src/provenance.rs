@@ -0,0 +1,149 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{iterate_pairs, rule::AntinodeRule, Pos, Puzzle};
+
+/// An antenna pair responsible for an antinode.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Source<const N: usize = 2> {
+    pub frequency: char,
+    pub pair: (Pos<N>, Pos<N>),
+}
+
+/// How much a single frequency contributes to the antinode count.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FrequencySummary {
+    /// The distinct antinodes created by this frequency
+    pub antinodes: usize,
+
+    /// How many of those antinodes are also created by another frequency
+    pub shared: usize,
+}
+
+/// Records which antenna pairs create each antinode, so the final count can
+/// be explained rather than just trusted.
+pub struct Provenance<const N: usize = 2> {
+    sources: HashMap<Pos<N>, Vec<Source<N>>>,
+}
+
+impl<const N: usize> Provenance<N> {
+    pub fn new(puzzle: &Puzzle<N>, rule: &dyn AntinodeRule<N>) -> Self {
+        let mut sources: HashMap<Pos<N>, Vec<Source<N>>> = HashMap::new();
+
+        for (frequency, positions) in puzzle.antennas_by_frequency() {
+            for (a, b) in iterate_pairs(&positions) {
+                let mut antinodes = rule.antinodes(puzzle, (a, b));
+                antinodes.sort();
+                antinodes.dedup();
+
+                for pos in antinodes {
+                    sources.entry(pos).or_default().push(Source {
+                        frequency,
+                        pair: (*a, *b),
+                    });
+                }
+            }
+        }
+
+        for pos_sources in sources.values_mut() {
+            pos_sources.sort();
+        }
+
+        Provenance { sources }
+    }
+
+    /// The number of distinct antinodes.
+    pub fn len(&self) -> usize {
+        self.sources.len()
+    }
+
+    /// The antenna pairs creating an antinode at a position, ordered by
+    /// frequency then pair. Empty if there is no antinode there.
+    pub fn sources_at(&self, pos: &Pos<N>) -> &[Source<N>] {
+        self.sources.get(pos).map_or(&[], Vec::as_slice)
+    }
+
+    /// Summarise the antinodes created by each frequency.
+    pub fn frequency_summary(&self) -> BTreeMap<char, FrequencySummary> {
+        let mut summary: BTreeMap<char, FrequencySummary> = BTreeMap::new();
+
+        for pos_sources in self.sources.values() {
+            let mut frequencies: Vec<char> = pos_sources.iter().map(|s| s.frequency).collect();
+            frequencies.dedup();
+
+            for frequency in frequencies.iter() {
+                let entry = summary.entry(*frequency).or_default();
+                entry.antinodes += 1;
+
+                if frequencies.len() > 1 {
+                    entry.shared += 1;
+                }
+            }
+        }
+
+        summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        rule::{DistanceRatio, Harmonics, Resonance},
+        tests::INPUT,
+    };
+
+    #[test]
+    fn test_sources_at() {
+        let puzzle: Puzzle = Puzzle::from(INPUT);
+        let provenance = Provenance::new(&puzzle, &DistanceRatio::new(2));
+
+        assert_eq!(provenance.len(), 14);
+
+        // The antinode on top of the top-most `A` comes from the `0` antennas
+        assert_eq!(
+            provenance.sources_at(&[6, 5]),
+            &[Source {
+                frequency: '0',
+                pair: ([7, 3], [8, 1]),
+            }]
+        );
+
+        assert_eq!(provenance.sources_at(&[0, 0]), &[]);
+    }
+
+    #[test]
+    fn test_frequency_summary_explains_the_count() {
+        let puzzle: Puzzle = Puzzle::from(INPUT);
+        let provenance = Provenance::new(&puzzle, &DistanceRatio::new(2));
+        let summary = provenance.frequency_summary();
+
+        assert_eq!(
+            summary[&'0'],
+            FrequencySummary {
+                antinodes: 10,
+                shared: 1,
+            }
+        );
+        assert_eq!(
+            summary[&'A'],
+            FrequencySummary {
+                antinodes: 5,
+                shared: 1,
+            }
+        );
+
+        // Shared antinodes are counted once per frequency
+        let total: usize = summary.values().map(|s| s.antinodes).sum();
+        assert_eq!(total - 1, provenance.len());
+    }
+
+    #[test]
+    fn test_multiple_pairs_of_one_frequency() {
+        let puzzle: Puzzle = Puzzle::from(INPUT);
+        let provenance = Provenance::new(&puzzle, &Harmonics::all(Resonance::Stepped));
+
+        // Every `0` antenna is an antinode of each pair it is part of
+        assert_eq!(provenance.sources_at(&[8, 1]).len(), 3);
+        assert_eq!(provenance.len(), 34);
+    }
+}
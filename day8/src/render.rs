@@ -0,0 +1,241 @@
+use std::collections::{BTreeMap, HashSet};
+
+use crate::{grid::AntinodeGrid, Pos, Puzzle, XY};
+
+const ANTINODE: char = '#';
+const EMPTY: char = '.';
+
+/// ANSI foreground colours, assigned to frequencies in order.
+const COLOURS: [u8; 12] = [31, 32, 33, 34, 35, 36, 91, 92, 93, 94, 95, 96];
+
+/// The antinodes created by each antenna frequency.
+pub type Overlay<const N: usize = 2> = BTreeMap<char, HashSet<Pos<N>>>;
+
+/// Draw the Puzzle with its antinodes marked as `#`, in the same style as the
+/// puzzle text. Antennas stay visible where they overlap an antinode.
+///
+/// With `colour`, each frequency's antennas and antinodes are drawn in their
+/// own ANSI colour. Antinodes created by more than one frequency are left
+/// uncoloured.
+pub fn render(puzzle: &Puzzle, overlay: &Overlay, colour: bool) -> String {
+    let frequencies: Vec<char> = puzzle.antennas_by_frequency().into_keys().collect();
+
+    let paint = |c: char, frequency: Option<char>| match frequency {
+        Some(frequency) if colour => {
+            let idx = frequencies.binary_search(&frequency).unwrap_or_default();
+            format!("\x1b[{}m{}\x1b[0m", COLOURS[idx % COLOURS.len()], c)
+        }
+        _ => c.to_string(),
+    };
+
+    (0..puzzle.dimensions[1])
+        .map(|y| {
+            (0..puzzle.dimensions[0])
+                .map(|x| {
+                    if let Some(&antenna) = puzzle.data.get(&[x, y]) {
+                        return paint(antenna, Some(antenna));
+                    }
+
+                    let mut sources = overlay
+                        .iter()
+                        .filter(|(_, antinodes)| antinodes.contains(&[x, y]))
+                        .map(|(frequency, _)| *frequency);
+
+                    match (sources.next(), sources.next()) {
+                        (None, _) => EMPTY.to_string(),
+                        (Some(frequency), None) => paint(ANTINODE, Some(frequency)),
+                        (Some(_), Some(_)) => paint(ANTINODE, None),
+                    }
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Parse a map with antinodes marked as `#`, such as the examples in the
+/// puzzle text. Returns the Puzzle without the antinodes, and the positions
+/// of the visible antinodes.
+pub fn parse_expected(input: &str) -> (Puzzle, HashSet<XY>) {
+    let mut puzzle = Puzzle::from(input);
+
+    let antinodes = puzzle
+        .antennas_by_frequency()
+        .remove(&ANTINODE)
+        .unwrap_or_default()
+        .into_iter()
+        .collect();
+
+    puzzle.data.retain(|_, c| *c != ANTINODE);
+
+    (puzzle, antinodes)
+}
+
+/// The differences between an expected map and computed antinodes.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct OverlayDiff {
+    /// Antinodes in the expected map that were not computed
+    pub missing: Vec<XY>,
+
+    /// Computed antinodes that are not in the expected map
+    pub unexpected: Vec<XY>,
+}
+
+impl OverlayDiff {
+    pub fn is_empty(&self) -> bool {
+        self.missing.is_empty() && self.unexpected.is_empty()
+    }
+}
+
+/// Compare computed antinodes with an expected map. Antinodes hidden beneath
+/// an antenna cannot appear in the map, so they are ignored.
+pub fn diff(expected: &str, antinodes: &AntinodeGrid) -> OverlayDiff {
+    let (puzzle, expected) = parse_expected(expected);
+
+    let visible: HashSet<XY> = antinodes
+        .iter()
+        .filter(|pos| !puzzle.data.contains_key(pos))
+        .collect();
+
+    let mut missing: Vec<XY> = expected.difference(&visible).copied().collect();
+    let mut unexpected: Vec<XY> = visible.difference(&expected).copied().collect();
+
+    missing.sort_by_key(|[x, y]| (*y, *x));
+    unexpected.sort_by_key(|[x, y]| (*y, *x));
+
+    OverlayDiff {
+        missing,
+        unexpected,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        find_antinodes, find_antinodes_by_frequency,
+        rule::{DistanceRatio, Harmonics, Resonance},
+        tests::INPUT,
+    };
+
+    const PART_ONE_MAP: &str = r#"
+        ......#....#
+        ...#....0...
+        ....#0....#.
+        ..#....0....
+        ....0....#..
+        .#....A.....
+        ...#........
+        #......#....
+        ........A...
+        .........A..
+        ..........#.
+        ..........#.
+    "#;
+
+    const PART_TWO_MAP: &str = r#"
+        ##....#....#
+        .#.#....0...
+        ..#.#0....#.
+        ..##...0....
+        ....0....#..
+        .#...#A....#
+        ...#..#.....
+        #....#.#....
+        ..#.....A...
+        ....#....A..
+        .#........#.
+        ...#......##
+    "#;
+
+    fn trim_map(map: &str) -> String {
+        map.trim()
+            .lines()
+            .map(str::trim)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn test_render_part_one() {
+        let puzzle: Puzzle = Puzzle::from(INPUT);
+        let overlay = find_antinodes_by_frequency(&puzzle, &DistanceRatio::new(2));
+
+        assert_eq!(render(&puzzle, &overlay, false), trim_map(PART_ONE_MAP));
+    }
+
+    #[test]
+    fn test_render_part_two() {
+        let puzzle: Puzzle = Puzzle::from(INPUT);
+        let overlay = find_antinodes_by_frequency(&puzzle, &Harmonics::all(Resonance::Stepped));
+
+        assert_eq!(render(&puzzle, &overlay, false), trim_map(PART_TWO_MAP));
+    }
+
+    #[test]
+    fn test_render_with_colour() {
+        let puzzle: Puzzle = Puzzle::from("a.a.");
+        let overlay = find_antinodes_by_frequency(&puzzle, &Harmonics::all(Resonance::Stepped));
+
+        assert_eq!(
+            render(&puzzle, &overlay, true),
+            "\x1b[31ma\x1b[0m.\x1b[31ma\x1b[0m."
+        );
+
+        let puzzle: Puzzle = Puzzle::from("a.a.b.b");
+        let overlay = find_antinodes_by_frequency(&puzzle, &DistanceRatio::new(2));
+
+        // (4, 0) is created by `a`, but hidden by a `b` antenna
+        assert_eq!(
+            render(&puzzle, &overlay, true),
+            "\x1b[31ma\x1b[0m.\x1b[31ma\x1b[0m.\x1b[32mb\x1b[0m.\x1b[32mb\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn test_render_shared_antinodes_without_colour() {
+        // Both frequencies create an antinode at (0, 0)
+        let puzzle: Puzzle = Puzzle::from("..a.a.\n......\nb.....\n......\nb.....\n......");
+        let overlay = find_antinodes_by_frequency(&puzzle, &DistanceRatio::new(2));
+
+        assert!(render(&puzzle, &overlay, true).starts_with("#."));
+    }
+
+    #[test]
+    fn test_parse_expected() {
+        let (puzzle, antinodes) = parse_expected(PART_ONE_MAP);
+
+        assert_eq!(puzzle.dimensions, [12, 12]);
+        assert_eq!(puzzle.data.len(), 7);
+        assert_eq!(antinodes.len(), 13);
+        assert!(antinodes.contains(&[6, 0]));
+    }
+
+    #[test]
+    fn test_diff_matches_puzzle_text() {
+        let puzzle: Puzzle = Puzzle::from(INPUT);
+
+        let antinodes = find_antinodes(&puzzle, &DistanceRatio::new(2));
+        assert!(diff(PART_ONE_MAP, &antinodes).is_empty());
+
+        let antinodes = find_antinodes(&puzzle, &Harmonics::all(Resonance::Stepped));
+        assert!(diff(PART_TWO_MAP, &antinodes).is_empty());
+    }
+
+    #[test]
+    fn test_diff_reports_differences() {
+        let puzzle: Puzzle = Puzzle::from(INPUT);
+        let antinodes = find_antinodes(&puzzle, &DistanceRatio::new(2));
+
+        // The first row has an antinode moved from (6, 0) to (0, 0)
+        let expected = PART_ONE_MAP.replacen("......#....#", "#..........#", 1);
+
+        assert_eq!(
+            diff(&expected, &antinodes),
+            OverlayDiff {
+                missing: vec![[0, 0]],
+                unexpected: vec![[6, 0]],
+            }
+        );
+    }
+}
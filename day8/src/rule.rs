@@ -0,0 +1,374 @@
+use crate::{Pos, Puzzle};
+
+/// Decides where a pair of antennas with the same frequency creates
+/// antinodes, in a Puzzle with `N` dimensions. Rules are shared between
+/// threads when frequencies are processed in parallel.
+pub trait AntinodeRule<const N: usize = 2>: Sync {
+    /// Find the antinodes for a pair of antennas. Antinodes cannot exist
+    /// outside the dimensions of the Puzzle.
+    fn antinodes(&self, puzzle: &Puzzle<N>, pair: (&Pos<N>, &Pos<N>)) -> Vec<Pos<N>>;
+}
+
+impl<R: AntinodeRule<N> + ?Sized, const N: usize> AntinodeRule<N> for Box<R> {
+    fn antinodes(&self, puzzle: &Puzzle<N>, pair: (&Pos<N>, &Pos<N>)) -> Vec<Pos<N>> {
+        self.as_ref().antinodes(puzzle, pair)
+    }
+}
+
+/// Antinodes occur at positions in line with both antennas that are `ratio`
+/// times as far from one antenna as from the other.
+///
+/// # Example
+///
+/// `#` represents the antinodes for antenna `a` with a ratio of 2.
+///
+/// ..........
+/// ...#......
+/// #.........
+/// ....a.....
+/// ........a.
+/// .....a....
+/// ..#.......
+/// ......#...
+/// ..........
+/// ..........
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DistanceRatio {
+    ratio: usize,
+    inner: bool,
+}
+
+impl DistanceRatio {
+    pub fn new(ratio: usize) -> Self {
+        DistanceRatio {
+            ratio,
+            inner: false,
+        }
+    }
+
+    /// Also include the positions between the two antennas. With a ratio of
+    /// 2, antennas at (0, 0) and (3, 3) have inner antinodes at (1, 1) and
+    /// (2, 2).
+    pub fn with_inner_points(mut self) -> Self {
+        self.inner = true;
+        self
+    }
+}
+
+impl<const N: usize> AntinodeRule<N> for DistanceRatio {
+    fn antinodes(&self, puzzle: &Puzzle<N>, pair: (&Pos<N>, &Pos<N>)) -> Vec<Pos<N>> {
+        let line = Line::new(pair, Resonance::Stepped);
+        let ratio = self.ratio as isize;
+
+        // Positions are fractions of the way from antenna 1 to antenna 2,
+        // as (numerator, denominator). Outside the antennas, the further
+        // antenna is `ratio` times as far as the nearer one at 1 + 1/(r-1)
+        // and -1/(r-1). Between them, it is at 1/(r+1) and r/(r+1).
+        let mut fractions = vec![];
+
+        if ratio > 1 {
+            fractions.push((ratio, ratio - 1));
+            fractions.push((-1, ratio - 1));
+        }
+
+        if self.inner {
+            fractions.push((1, ratio + 1));
+            fractions.push((ratio, ratio + 1));
+        }
+
+        let mut antinodes: Vec<Pos<N>> = fractions
+            .into_iter()
+            .filter_map(|(numerator, denominator)| line.fraction(numerator, denominator))
+            .filter_map(|pos| puzzle.checked_pos(pos))
+            .collect();
+
+        // A ratio of 1 gives the midpoint twice
+        antinodes.dedup();
+        antinodes
+    }
+}
+
+/// How closely spaced the antinodes of a pair of antennas are once resonant
+/// harmonics are taken into account.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Resonance {
+    /// Antinodes occur at every multiple of the distance between the two
+    /// antennas.
+    Stepped,
+
+    /// Antinodes occur at every grid position exactly in line with the two
+    /// antennas, including any that fall between multiples of the distance.
+    Collinear,
+}
+
+/// Antinodes occur at multiples of the distance between the two antennas,
+/// measured outwards from the nearer antenna. The antennas themselves are
+/// the 0th multiple.
+///
+/// # Example
+///
+/// `#` represents the antinodes for antenna `T` with every multiple.
+///
+/// T....#....
+/// ...T......
+/// .T....#...
+/// .........#
+/// ..#.......
+/// ..........
+/// ...#......
+/// ..........
+/// ....#.....
+/// ..........
+///
+/// With `Resonance::Collinear`, the distance is reduced by the greatest
+/// common divisor of dx and dy, so every grid position on the line is
+/// included. For antennas at (0, 0) and (2, 2), `Stepped` yields (0, 0),
+/// (2, 2), (4, 4)... while `Collinear` also yields (1, 1), (3, 3)...
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Harmonics {
+    /// The multiples to include, or `None` for all of them
+    multiples: Option<Vec<usize>>,
+    resonance: Resonance,
+}
+
+impl Harmonics {
+    pub fn all(resonance: Resonance) -> Self {
+        Harmonics {
+            multiples: None,
+            resonance,
+        }
+    }
+
+    /// Only include the given multiples, e.g. `[2, 3]` for the 2nd and 3rd.
+    pub fn only(multiples: impl IntoIterator<Item = usize>, resonance: Resonance) -> Self {
+        Harmonics {
+            multiples: Some(multiples.into_iter().collect()),
+            resonance,
+        }
+    }
+
+    fn includes(&self, multiple: usize) -> bool {
+        self.multiples
+            .as_ref()
+            .is_none_or(|multiples| multiples.contains(&multiple))
+    }
+}
+
+impl<const N: usize> AntinodeRule<N> for Harmonics {
+    fn antinodes(&self, puzzle: &Puzzle<N>, pair: (&Pos<N>, &Pos<N>)) -> Vec<Pos<N>> {
+        let line = Line::new(pair, self.resonance);
+        let mut antinodes = Vec::new();
+
+        // Moving from antenna 1 towards (and past) antenna 2, then away from
+        // antenna 2 on the far side of antenna 1.
+        for direction in [1, -1] {
+            let mut steps = if direction == 1 { 0 } else { -1 };
+
+            while let Some(pos) = puzzle.checked_pos(line.step(steps)) {
+                if self.includes(line.multiple(steps)) {
+                    antinodes.push(pos);
+                }
+
+                steps += direction;
+            }
+        }
+
+        antinodes
+    }
+}
+
+/// Limits another rule to antinodes within `max_range` of the nearer antenna
+/// of the pair. The range is measured as the largest distance along any one
+/// axis.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MaxRange<R> {
+    rule: R,
+    max_range: usize,
+}
+
+impl<R> MaxRange<R> {
+    pub fn new(rule: R, max_range: usize) -> Self {
+        MaxRange { rule, max_range }
+    }
+}
+
+impl<R: AntinodeRule<N>, const N: usize> AntinodeRule<N> for MaxRange<R> {
+    fn antinodes(&self, puzzle: &Puzzle<N>, pair: (&Pos<N>, &Pos<N>)) -> Vec<Pos<N>> {
+        let range = |a: &Pos<N>, b: &Pos<N>| {
+            a.iter()
+                .zip(b)
+                .map(|(a, b)| a.abs_diff(*b))
+                .max()
+                .unwrap_or(0)
+        };
+
+        self.rule
+            .antinodes(puzzle, pair)
+            .into_iter()
+            .filter(|pos| range(pos, pair.0).min(range(pos, pair.1)) <= self.max_range)
+            .collect()
+    }
+}
+
+/// The line through a pair of antennas, split into equal steps.
+struct Line<const N: usize> {
+    start: [isize; N],
+    step: [isize; N],
+
+    /// The number of steps from antenna 1 to antenna 2
+    steps_between: isize,
+}
+
+impl<const N: usize> Line<N> {
+    fn new((a, b): (&Pos<N>, &Pos<N>), resonance: Resonance) -> Self {
+        let start = a.map(|val| val as isize);
+        let delta: [isize; N] = std::array::from_fn(|axis| b[axis] as isize - start[axis]);
+
+        let steps_between = match resonance {
+            Resonance::Stepped => 1,
+            Resonance::Collinear => delta
+                .iter()
+                .fold(0, |acc, val| gcd(acc, val.unsigned_abs()))
+                .max(1) as isize,
+        };
+
+        Line {
+            start,
+            step: delta.map(|val| val / steps_between),
+            steps_between,
+        }
+    }
+
+    /// The position a number of steps from antenna 1 towards antenna 2.
+    fn step(&self, steps: isize) -> [isize; N] {
+        std::array::from_fn(|axis| self.start[axis] + self.step[axis] * steps)
+    }
+
+    /// The number of steps from the nearer antenna to a position.
+    fn multiple(&self, steps: isize) -> usize {
+        if steps < 0 {
+            steps.unsigned_abs()
+        } else if steps > self.steps_between {
+            (steps - self.steps_between) as usize
+        } else {
+            steps.min(self.steps_between - steps) as usize
+        }
+    }
+
+    /// The position a fraction of the way from antenna 1 to antenna 2, if
+    /// it lands exactly on the grid.
+    fn fraction(&self, numerator: isize, denominator: isize) -> Option<[isize; N]> {
+        let delta = self.step.map(|step| step * self.steps_between * numerator);
+
+        if delta.iter().any(|val| val % denominator != 0) {
+            return None;
+        }
+
+        Some(std::array::from_fn(|axis| {
+            self.start[axis] + delta[axis] / denominator
+        }))
+    }
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::XY;
+
+    fn antinodes(rule: &impl AntinodeRule, pair: (&XY, &XY)) -> HashSet<XY> {
+        let puzzle = Puzzle::from(
+            r#"
+            ..........
+            ..........
+            ..........
+            ..........
+            ..........
+            ..........
+            ..........
+            ..........
+            ..........
+            ..........
+            "#,
+        );
+
+        rule.antinodes(&puzzle, pair).into_iter().collect()
+    }
+
+    #[test]
+    fn test_distance_ratio() {
+        let rule = DistanceRatio::new(2);
+        assert_eq!(
+            antinodes(&rule, (&[3, 3], &[5, 4])),
+            HashSet::from([[1, 2], [7, 5]])
+        );
+
+        let rule = DistanceRatio::new(3);
+        assert_eq!(
+            antinodes(&rule, (&[2, 2], &[4, 4])),
+            HashSet::from([[1, 1], [5, 5]])
+        );
+
+        // The points three times as far from one antenna as the other fall at
+        // (1.5, 1.5) and (3.5, 3.5), between grid positions
+        let rule = DistanceRatio::new(3);
+        assert_eq!(antinodes(&rule, (&[2, 2], &[3, 3])), HashSet::from([]));
+    }
+
+    #[test]
+    fn test_distance_ratio_with_inner_points() {
+        let rule = DistanceRatio::new(2).with_inner_points();
+        assert_eq!(
+            antinodes(&rule, (&[0, 0], &[3, 6])),
+            HashSet::from([[1, 2], [2, 4]])
+        );
+
+        // A ratio of 1 is the midpoint
+        let rule = DistanceRatio::new(1).with_inner_points();
+        assert_eq!(
+            antinodes(&rule, (&[2, 2], &[4, 6])),
+            HashSet::from([[3, 4]])
+        );
+    }
+
+    #[test]
+    fn test_only_some_harmonics() {
+        let rule = Harmonics::only([2, 3], Resonance::Stepped);
+
+        // (1, -2) and (0, -3) are off the map
+        assert_eq!(
+            antinodes(&rule, (&[3, 0], &[4, 1])),
+            HashSet::from([[6, 3], [7, 4]])
+        );
+    }
+
+    #[test]
+    fn test_only_some_harmonics_collinear() {
+        // Antennas 4 steps apart, so the 2nd multiple includes the midpoint
+        let rule = Harmonics::only([2], Resonance::Collinear);
+
+        assert_eq!(
+            antinodes(&rule, (&[2, 2], &[6, 6])),
+            HashSet::from([[0, 0], [4, 4], [8, 8]])
+        );
+    }
+
+    #[test]
+    fn test_max_range() {
+        let rule = MaxRange::new(Harmonics::all(Resonance::Stepped), 2);
+
+        assert_eq!(
+            antinodes(&rule, (&[4, 4], &[5, 5])),
+            HashSet::from([[2, 2], [3, 3], [4, 4], [5, 5], [6, 6], [7, 7]])
+        );
+    }
+}
@@ -1,9 +1,25 @@
+pub mod sections;
+
 use std::{env, fs};
 
-pub fn read_file_from_args() -> String {
-    let filename = env::args()
+pub fn filename_from_args() -> String {
+    env::args()
         .nth(1)
-        .expect("Please provide a filename as an argument");
+        .expect("Please provide a filename as an argument")
+}
+
+pub fn read_file_from_args() -> String {
+    fs::read_to_string(filename_from_args()).expect("Failed to read file")
+}
+
+/// Determine if a flag (e.g. `--verbose`) was passed after the filename.
+pub fn has_flag(flag: &str) -> bool {
+    env::args().skip(2).any(|arg| arg == flag)
+}
 
-    fs::read_to_string(filename).expect("Failed to read file")
+/// Get the value of a flag passed after the filename as `--flag=value`.
+pub fn flag_value(flag: &str) -> Option<String> {
+    env::args()
+        .skip(2)
+        .find_map(|arg| Some(arg.strip_prefix(flag)?.strip_prefix('=')?.to_string()))
 }
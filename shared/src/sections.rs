@@ -0,0 +1,178 @@
+use std::fmt;
+
+/// A line of input, numbered from 1 as it appears in the file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Line<'a> {
+    pub number: usize,
+    pub text: &'a str,
+}
+
+/// A section of input, numbered from 1.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Section<'a> {
+    pub number: usize,
+    pub lines: Vec<Line<'a>>,
+}
+
+/// An error in a particular section and line of the input.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub section: usize,
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "section {}, line {}: {}",
+            self.section, self.line, self.message
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl Section<'_> {
+    /// Parse each line of the section, reporting the first failure with its
+    /// section and line number.
+    pub fn parse_lines<T>(
+        &self,
+        parse: impl Fn(&str) -> Result<T, String>,
+    ) -> Result<Vec<T>, ParseError> {
+        self.lines
+            .iter()
+            .map(|line| {
+                parse(line.text).map_err(|message| ParseError {
+                    section: self.number,
+                    line: line.number,
+                    message,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Split input into a number of sections separated by blank lines.
+///
+/// Lines are trimmed, so Windows line endings and indentation are ignored,
+/// and lines starting with `#` are comments and are dropped. Any number of
+/// blank lines can separate sections, and blank lines before the first or
+/// after the last section are ignored. Once the last section has started,
+/// blank lines within it are kept as empty lines, for inputs where a blank
+/// line means something.
+pub fn split_sections(input: &str, count: usize) -> Result<Vec<Section<'_>>, ParseError> {
+    let mut sections: Vec<Section> = Vec::new();
+    let mut blank_lines: Vec<Line> = Vec::new();
+    let mut last_number = 0;
+
+    for (idx, text) in input.lines().enumerate() {
+        let line = Line {
+            number: idx + 1,
+            text: text.trim(),
+        };
+        last_number = line.number;
+
+        if line.text.starts_with('#') {
+            continue;
+        }
+
+        if line.text.is_empty() {
+            blank_lines.push(line);
+            continue;
+        }
+
+        let in_last_section = sections.len() == count;
+
+        if let Some(section) = sections.last_mut() {
+            if blank_lines.is_empty() || in_last_section {
+                section.lines.append(&mut blank_lines);
+                section.lines.push(line);
+                continue;
+            }
+        }
+
+        // Blank lines between sections are dropped
+        blank_lines.clear();
+        sections.push(Section {
+            number: sections.len() + 1,
+            lines: vec![line],
+        });
+    }
+
+    if sections.len() < count {
+        return Err(ParseError {
+            section: sections.len() + 1,
+            line: last_number + 1,
+            message: format!("Expected {} sections, found {}", count, sections.len()),
+        });
+    }
+
+    Ok(sections)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts<'a>(section: &Section<'a>) -> Vec<&'a str> {
+        section.lines.iter().map(|line| line.text).collect()
+    }
+
+    #[test]
+    fn test_split_sections() {
+        let sections = split_sections("1|2\n3|4\n\n1,2,3\n4,5,6\n", 2).unwrap();
+
+        assert_eq!(texts(&sections[0]), vec!["1|2", "3|4"]);
+        assert_eq!(texts(&sections[1]), vec!["1,2,3", "4,5,6"]);
+        assert_eq!(sections[1].number, 2);
+        assert_eq!(sections[1].lines[0].number, 4);
+    }
+
+    #[test]
+    fn test_windows_line_endings() {
+        let sections = split_sections("1|2\r\n3|4\r\n\r\n1,2,3\r\n", 2).unwrap();
+
+        assert_eq!(texts(&sections[0]), vec!["1|2", "3|4"]);
+        assert_eq!(texts(&sections[1]), vec!["1,2,3"]);
+    }
+
+    #[test]
+    fn test_extra_blank_lines_and_comments() {
+        let input = "\n\n# Rules\n1|2\n# More rules\n3|4\n\n  \n\n# Updates\n1,2,3\n\n4,5,6\n\n\n";
+        let sections = split_sections(input, 2).unwrap();
+
+        assert_eq!(texts(&sections[0]), vec!["1|2", "3|4"]);
+
+        // Blank lines within the last section are kept
+        assert_eq!(texts(&sections[1]), vec!["1,2,3", "", "4,5,6"]);
+        assert_eq!(sections[1].lines[2].number, 13);
+    }
+
+    #[test]
+    fn test_missing_section() {
+        assert_eq!(
+            split_sections("1|2\n3|4\n", 2),
+            Err(ParseError {
+                section: 2,
+                line: 3,
+                message: "Expected 2 sections, found 1".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_lines_reports_section_and_line() {
+        let sections = split_sections("1\n2\n\n3\n# Comment\nfour\n", 2).unwrap();
+        let parse = |text: &str| text.parse::<usize>().map_err(|err| err.to_string());
+
+        assert_eq!(sections[0].parse_lines(parse), Ok(vec![1, 2]));
+
+        let err = sections[1].parse_lines(parse).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "section 2, line 6: invalid digit found in string"
+        );
+    }
+}